@@ -1,7 +1,143 @@
 use std::fmt;
+use std::ops::RangeInclusive;
 
-use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use serialport::{SerialPortInfo, SerialPortType};
+use specta::Type;
 
+use crate::error::{Error, Result};
+use crate::pelco_camera::PelcoCamera;
+use crate::pelco_p_camera::PelcoPCamera;
+use crate::transport::{SerialTransport, TcpTransport};
+#[cfg(feature = "visca")]
+use crate::visca_camera::ViscaCamera;
+
+/// A port a camera can be opened on, with whatever the OS reports about the adapter behind it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Type)]
+pub struct PortInfo {
+    pub name: String,
+    pub vid: Option<u16>,
+    pub pid: Option<u16>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    /// Stays the same when the adapter is plugged into another socket, unlike `name`.
+    pub serial_number: Option<String>,
+}
+
+impl PortInfo {
+    /// A port known only by its name, such as a network endpoint.
+    pub fn named(name: &str) -> Self {
+        Self {
+            name: String::from(name),
+            ..Self::default()
+        }
+    }
+}
+
+impl From<SerialPortInfo> for PortInfo {
+    fn from(info: SerialPortInfo) -> Self {
+        match info.port_type {
+            SerialPortType::UsbPort(usb) => Self {
+                name: info.port_name,
+                vid: Some(usb.vid),
+                pid: Some(usb.pid),
+                manufacturer: usb.manufacturer,
+                product: usb.product,
+                serial_number: usb.serial_number,
+            },
+            _ => Self::named(&info.port_name),
+        }
+    }
+}
+
+/// Lists the serial ports a camera can be opened on, along with any virtual ports.
+pub fn available_ports() -> Result<Vec<PortInfo>> {
+    #[allow(unused_mut)]
+    let mut ports: Vec<PortInfo> = serialport::available_ports()?
+        .into_iter()
+        .map(PortInfo::from)
+        .collect();
+
+    #[cfg(feature = "visca")]
+    ports.push(PortInfo::named(crate::visca::SIMULATOR_PORT));
+
+    Ok(ports)
+}
+
+/// Parses bytes typed as hex, such as `81 01 04 00 02 FF` or `0x81,0x01`.
+pub fn parse_hex(input: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+
+    for token in input
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+    {
+        let digits = token
+            .strip_prefix("0x")
+            .or_else(|| token.strip_prefix("0X"))
+            .unwrap_or(token);
+
+        if digits.is_empty()
+            || digits.len() % 2 != 0
+            || !digits.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            return Err(Error::InvalidHex(token.to_string()));
+        }
+
+        // Allow runs like `8101040002FF` as well as space-separated bytes
+        for index in (0..digits.len()).step_by(2) {
+            bytes.push(
+                u8::from_str_radix(&digits[index..index + 2], 16)
+                    .map_err(|_| Error::InvalidHex(token.to_string()))?,
+            );
+        }
+    }
+
+    Ok(bytes)
+}
+
+pub fn format_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// What came back from the camera after writing raw bytes to it.
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct RawReply {
+    pub bytes: String,
+    pub decoded: Option<Vec<String>>,
+}
+
+pub const MAX_PRESET_SPEED: u8 = 24;
+pub const MAX_AUXILIARY: u8 = 8;
+
+/// How fast to drive the camera.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize, Type)]
+pub enum Speed {
+    Slow,
+    #[default]
+    Medium,
+    Fast,
+    /// Faster than the normal pan range on cameras that have a turbo pan; other motions treat it
+    /// as their fastest speed.
+    Turbo,
+}
+
+/// How much of the camera to reset.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Type)]
+pub enum ResetKind {
+    /// Recalibrates zoom and focus.
+    Lens,
+    /// Restarts the camera, keeping its settings.
+    Camera,
+    /// Erases presets and settings, returning the camera to how it shipped.
+    FactoryDefaults,
+}
+
+#[derive(Debug, Clone, Copy)]
 pub enum Direction {
     Down,
     Up,
@@ -9,20 +145,182 @@ pub enum Direction {
     Right,
 }
 
+/// Something that happened on the camera without the app asking for it, such as a button
+/// pressed on the IR remote.
+#[derive(Debug, Clone, Copy)]
+pub enum CameraEvent {
+    Power(bool),
+    NetworkChange,
+}
+
+pub type EventHandler = Box<dyn Fn(CameraEvent) + Send + 'static>;
+
+/// Cancels whatever command the camera is still working on, from a thread other than the one
+/// waiting for it.
+pub type Interrupter = Box<dyn Fn() -> Result<()> + Send + Sync + 'static>;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize, Type)]
+pub enum Protocol {
+    #[default]
+    PelcoD,
+    PelcoP,
+    #[cfg(feature = "visca")]
+    Visca,
+}
+
+impl Protocol {
+    pub fn all() -> Vec<Protocol> {
+        vec![
+            Protocol::PelcoD,
+            Protocol::PelcoP,
+            #[cfg(feature = "visca")]
+            Protocol::Visca,
+        ]
+    }
+
+    /// The addresses a camera speaking this protocol can be set to.
+    pub fn addresses(self) -> RangeInclusive<u8> {
+        match self {
+            Protocol::PelcoD => 1..=255,
+            // Pelco-P numbers its addresses from 0
+            Protocol::PelcoP => 0..=31,
+            #[cfg(feature = "visca")]
+            Protocol::Visca => 1..=7,
+        }
+    }
+
+    pub fn validate_address(self, address: u8) -> Result<u8> {
+        if self.addresses().contains(&address) {
+            Ok(address)
+        } else {
+            Err(Error::InvalidAddress(address))
+        }
+    }
+
+    pub fn open(self, path: &str, address: u8) -> Result<Box<dyn Camera>> {
+        let address = self.validate_address(address)?;
+
+        // The simulator only speaks VISCA, whatever protocol is selected
+        #[cfg(feature = "visca")]
+        if path == crate::visca::SIMULATOR_PORT {
+            return Ok(Box::new(ViscaCamera::simulated(address)));
+        }
+
+        Ok(match (self, is_network_address(path)) {
+            (Protocol::PelcoD, false) => {
+                Box::new(PelcoCamera::<SerialTransport>::new(path, address)?)
+            }
+            (Protocol::PelcoD, true) => Box::new(PelcoCamera::<TcpTransport>::new(path, address)?),
+            (Protocol::PelcoP, false) => {
+                Box::new(PelcoPCamera::<SerialTransport>::new(path, address)?)
+            }
+            (Protocol::PelcoP, true) => Box::new(PelcoPCamera::<TcpTransport>::new(path, address)?),
+            #[cfg(feature = "visca")]
+            (Protocol::Visca, false) => {
+                Box::new(ViscaCamera::<SerialTransport>::new(path, address)?)
+            }
+            #[cfg(feature = "visca")]
+            (Protocol::Visca, true) => Box::new(ViscaCamera::<TcpTransport>::new(path, address)?),
+        })
+    }
+}
+
+/// Whether `path` names a `host:port` endpoint rather than a serial port.
+pub fn is_network_address(path: &str) -> bool {
+    path.rsplit_once(':')
+        .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok())
+}
+
 pub trait Camera: Send {
-    fn new(path: &str) -> Result<Self>
+    /// Opens the camera at `address` on the bus behind `path`.
+    fn new(path: &str, address: u8) -> Result<Self>
     where
         Self: Sized;
     fn name(&self) -> Option<String>;
     fn power_on(&mut self) -> Result<()>;
     fn power_off(&mut self) -> Result<()>;
     fn autofocus(&mut self, state: bool) -> Result<()>;
-    fn zoom_in(&mut self) -> Result<()>;
-    fn zoom_out(&mut self) -> Result<()>;
-    fn pan_tilt(&mut self, direction: Direction) -> Result<()>;
-    fn stop(&mut self) -> Result<()>;
+    fn zoom_in(&mut self, speed: Speed) -> Result<()>;
+    fn zoom_out(&mut self, speed: Speed) -> Result<()>;
+    fn pan_tilt(&mut self, direction: Direction, speed: Speed) -> Result<()>;
+
+    /// Sets how fast later focus moves run.
+    fn set_focus_speed(&mut self, _speed: Speed) -> Result<()> {
+        Err(Error::Unsupported(String::from("Focus speed")))
+    }
+
+    /// Stops panning and tilting, leaving any zoom or focus move running.
+    fn stop_pan_tilt(&mut self) -> Result<()>;
+
+    /// Stops zooming, leaving any pan, tilt or focus move running.
+    fn stop_zoom(&mut self) -> Result<()>;
+
+    /// Stops a manual focus move, leaving the head and zoom running.
+    fn stop_focus(&mut self) -> Result<()>;
+
+    /// Stops every axis at once.
+    fn stop(&mut self) -> Result<()> {
+        self.stop_pan_tilt()?;
+        self.stop_zoom()?;
+        self.stop_focus()
+    }
+
     fn set_preset(&mut self, preset: u8) -> Result<()>;
     fn go_to_preset(&mut self, preset: u8) -> Result<()>;
+
+    /// Sets how fast `preset` is recalled, from 1 (slowest) to [`MAX_PRESET_SPEED`].
+    fn set_preset_speed(&mut self, preset: u8, speed: u8) -> Result<()>;
+
+    fn set_tally(&mut self, state: bool) -> Result<()>;
+
+    /// Shows the camera's on-screen menu. While it is open, [`Camera::menu_navigate`] moves the
+    /// highlight instead of the head.
+    fn open_menu(&mut self) -> Result<()>;
+    fn close_menu(&mut self) -> Result<()>;
+    fn menu_enter(&mut self) -> Result<()>;
+    fn menu_back(&mut self) -> Result<()>;
+    fn menu_navigate(&mut self, direction: Direction) -> Result<()>;
+
+    /// Switches auxiliary output `channel` (a relay, wiper or light on the housing) on or off.
+    fn auxiliary(&mut self, _channel: u8, _state: bool) -> Result<()> {
+        Err(Error::Unsupported(String::from("Auxiliary output")))
+    }
+
+    /// Starts recording pattern `pattern` from the moves that follow, until [`Camera::stop_pattern`].
+    fn start_pattern(&mut self, _pattern: u8) -> Result<()> {
+        Err(Error::Unsupported(String::from("Patterns")))
+    }
+
+    fn stop_pattern(&mut self, _pattern: u8) -> Result<()> {
+        Err(Error::Unsupported(String::from("Patterns")))
+    }
+
+    /// Replays a recorded pattern in a loop until the camera is moved or stopped.
+    fn run_pattern(&mut self, _pattern: u8) -> Result<()> {
+        Err(Error::Unsupported(String::from("Patterns")))
+    }
+
+    /// Cycles through the presets configured on the camera until it is moved or stopped.
+    fn start_tour(&mut self) -> Result<()> {
+        Err(Error::Unsupported(String::from("Preset tours")))
+    }
+
+    fn reset(&mut self, _kind: ResetKind) -> Result<()> {
+        Err(Error::Unsupported(String::from("Reset")))
+    }
+
+    /// Writes `bytes` to the port as-is and returns whatever the camera sends back.
+    fn send_raw(&mut self, bytes: &[u8]) -> Result<RawReply>;
+
+    /// Registers a handler for unsolicited camera events. Protocols that never report anything
+    /// on their own ignore it.
+    fn on_event(&mut self, _handler: EventHandler) {}
+
+    /// Returns an [`Interrupter`] for commands in flight. Protocols that don't wait on the
+    /// camera have nothing to interrupt.
+    fn interrupter(&self) -> Option<Interrupter> {
+        None
+    }
 }
 
 impl fmt::Debug for dyn Camera {
@@ -36,3 +334,80 @@ impl fmt::Debug for dyn Camera {
         write!(f, ")")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serialport::UsbPortInfo;
+    use test_case::test_case;
+
+    #[test_case("81 01 04 00 02 FF" => matches Ok(v) if v == b"\x81\x01\x04\x00\x02\xFF"; "spaced")]
+    #[test_case("0x81,0x01, 0xff" => matches Ok(v) if v == b"\x81\x01\xFF"; "prefixed")]
+    #[test_case("8101040002FF" => matches Ok(v) if v == b"\x81\x01\x04\x00\x02\xFF"; "packed")]
+    #[test_case("" => matches Ok(v) if v.is_empty(); "empty")]
+    #[test_case("81 0" => matches Err(Error::InvalidHex(_)); "odd digit")]
+    #[test_case("81 GG" => matches Err(Error::InvalidHex(_)); "not hex")]
+    fn test_parse_hex(input: &str) -> Result<Vec<u8>> {
+        parse_hex(input)
+    }
+
+    #[test_case("192.168.1.20:4001" => true; "ipv4")]
+    #[test_case("nport.local:950" => true; "hostname")]
+    #[test_case("/dev/ttyUSB0" => false; "unix serial")]
+    #[test_case("COM3" => false; "windows serial")]
+    #[test_case(":4001" => false; "no host")]
+    #[test_case("nport.local:serial" => false; "no port")]
+    fn test_is_network_address(path: &str) -> bool {
+        is_network_address(path)
+    }
+
+    #[test_case(Protocol::PelcoD, 1 => matches Ok(1); "pelco-d")]
+    #[test_case(Protocol::PelcoD, 0 => matches Err(Error::InvalidAddress(0)); "pelco-d zero")]
+    #[test_case(Protocol::PelcoP, 0 => matches Ok(0); "pelco-p zero")]
+    #[test_case(Protocol::PelcoP, 32 => matches Err(Error::InvalidAddress(32)); "pelco-p too high")]
+    fn test_validate_address(protocol: Protocol, address: u8) -> Result<u8> {
+        protocol.validate_address(address)
+    }
+
+    #[test]
+    fn test_port_info_from_usb() {
+        let info = SerialPortInfo {
+            port_name: String::from("/dev/ttyUSB0"),
+            port_type: SerialPortType::UsbPort(UsbPortInfo {
+                vid: 0x0403,
+                pid: 0x6001,
+                serial_number: Some(String::from("A10KZP5B")),
+                manufacturer: Some(String::from("FTDI")),
+                product: Some(String::from("FT232R USB UART")),
+            }),
+        };
+
+        assert_eq!(
+            PortInfo::from(info),
+            PortInfo {
+                name: String::from("/dev/ttyUSB0"),
+                vid: Some(0x0403),
+                pid: Some(0x6001),
+                manufacturer: Some(String::from("FTDI")),
+                product: Some(String::from("FT232R USB UART")),
+                serial_number: Some(String::from("A10KZP5B")),
+            }
+        );
+    }
+
+    #[test]
+    fn test_port_info_from_builtin() {
+        let info = SerialPortInfo {
+            port_name: String::from("COM1"),
+            port_type: SerialPortType::PciPort,
+        };
+
+        assert_eq!(PortInfo::from(info), PortInfo::named("COM1"));
+    }
+
+    #[test]
+    fn test_format_hex() {
+        assert_eq!(format_hex(b"\x90\x41\xFF"), "90 41 FF");
+    }
+}
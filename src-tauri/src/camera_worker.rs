@@ -0,0 +1,473 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    thread,
+};
+
+use log::debug;
+
+use crate::{
+    camera::{Camera, Interrupter},
+    error::{Error, Result},
+};
+
+/// Talks to the camera, then hands back whatever reports the outcome.
+type Run = Box<dyn FnOnce(&mut dyn Camera) -> Report + Send>;
+type Report = Box<dyn FnOnce() + Send>;
+
+/// The part of the camera a move drives, so stopping it only clears what it stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    PanTilt,
+    Zoom,
+}
+
+/// How a job is queued, and what it clears from the queue on its way in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobKind {
+    /// Runs in turn behind everything queued before it.
+    Command,
+    /// Starts an axis moving. Dropped if a stop for the same axis arrives while it is queued.
+    Drive(Axis),
+    /// Heads for a preset. Dropped if any stop arrives while it is queued.
+    Recall,
+    /// Stops an axis. Runs ahead of every queued job, discards that axis's queued drives and any
+    /// queued recall, and cancels one of those in flight. Never discards another stop.
+    Stop(Axis),
+    /// Stops everything. Runs ahead of every queued job, discards them all and cancels whatever
+    /// is in flight.
+    Halt,
+}
+
+impl JobKind {
+    fn is_stop(self) -> bool {
+        matches!(self, JobKind::Stop(_) | JobKind::Halt)
+    }
+}
+
+/// How many times each part of the queue has been flushed.
+#[derive(Default)]
+struct Flushes {
+    all: AtomicU64,
+    pan_tilt: AtomicU64,
+    zoom: AtomicU64,
+    recalls: AtomicU64,
+}
+
+impl Flushes {
+    fn axis(&self, axis: Axis) -> &AtomicU64 {
+        match axis {
+            Axis::PanTilt => &self.pan_tilt,
+            Axis::Zoom => &self.zoom,
+        }
+    }
+
+    /// The count a stop raises to discard queued jobs of `kind`, if a stop short of a halt can.
+    fn of(&self, kind: JobKind) -> Option<&AtomicU64> {
+        match kind {
+            JobKind::Drive(axis) => Some(self.axis(axis)),
+            JobKind::Recall => Some(&self.recalls),
+            JobKind::Command | JobKind::Stop(_) | JobKind::Halt => None,
+        }
+    }
+}
+
+struct Job {
+    kind: JobKind,
+    /// How many times the whole queue had been flushed when this job joined it.
+    flushes: u64,
+    /// How many times jobs of this kind had been flushed by a stop when it joined the queue.
+    kind_flushes: u64,
+    run: Run,
+}
+
+impl Job {
+    /// Whether a flush since this job was queued has discarded it.
+    fn is_flushed(&self, flushes: &Flushes) -> bool {
+        self.flushes < flushes.all.load(Ordering::SeqCst)
+            || flushes
+                .of(self.kind)
+                .is_some_and(|count| self.kind_flushes < count.load(Ordering::SeqCst))
+    }
+}
+
+/// What the worker thread and its handle share.
+struct Shared {
+    camera: Mutex<Option<Box<dyn Camera>>>,
+    flushes: Flushes,
+    /// The kind of job the camera is working on, if any.
+    running: Mutex<Option<JobKind>>,
+    /// Stops waiting to run, which the worker takes before anything left in the channel.
+    stops: Mutex<VecDeque<Job>>,
+    /// Set once the handle is dropped, so the worker closes the camera as soon as it is free.
+    closed: AtomicBool,
+}
+
+impl Shared {
+    /// Runs `job` unless a flush has discarded it, and returns false once the camera is closed.
+    fn run(&self, job: Job) -> bool {
+        {
+            // Checked under the same lock a stop interrupts under, so a stop either discards
+            // this job or sees it running
+            let mut running = self.running.lock().expect("mutex poisoned");
+            if job.is_flushed(&self.flushes) {
+                return true;
+            }
+            *running = Some(job.kind);
+        }
+
+        let report = {
+            let mut camera = self.camera.lock().expect("mutex poisoned");
+            if self.closed.load(Ordering::SeqCst) {
+                *camera = None;
+            }
+            let report = match camera.as_mut() {
+                Some(camera) => (job.run)(camera.as_mut()),
+                None => return false,
+            };
+            if self.closed.load(Ordering::SeqCst) {
+                *camera = None;
+            }
+            report
+        };
+        *self.running.lock().expect("mutex poisoned") = None;
+
+        // Reports take the UI state's lock, so never make them with the camera held
+        report();
+        true
+    }
+
+    /// Runs every waiting stop, and returns false once the camera is closed.
+    fn run_stops(&self) -> bool {
+        loop {
+            let stop = self.stops.lock().expect("mutex poisoned").pop_front();
+            match stop {
+                Some(stop) => {
+                    if !self.run(stop) {
+                        return false;
+                    }
+                }
+                None => return true,
+            }
+        }
+    }
+}
+
+/// Runs an open camera on a thread of its own, so slow I/O never holds the UI state's lock.
+/// Jobs run one at a time in the order they were queued, except that stops jump the queue and
+/// discard whatever they stop.
+pub struct CameraWorker {
+    name: Option<String>,
+    interrupter: Option<Interrupter>,
+    shared: Arc<Shared>,
+    /// Carries queued jobs, or `None` to wake the worker for a stop.
+    jobs: Sender<Option<Job>>,
+}
+
+impl CameraWorker {
+    pub fn spawn(camera: Box<dyn Camera>) -> Self {
+        let name = camera.name();
+        let interrupter = camera.interrupter();
+        let shared = Arc::new(Shared {
+            camera: Mutex::new(Some(camera)),
+            flushes: Flushes::default(),
+            running: Mutex::new(None),
+            stops: Mutex::new(VecDeque::new()),
+            closed: AtomicBool::new(false),
+        });
+        let (jobs, receiver) = mpsc::channel::<Option<Job>>();
+
+        let worker = Arc::clone(&shared);
+        thread::spawn(move || {
+            for job in receiver {
+                // Stops go first, however long the channel has grown
+                if !worker.run_stops() {
+                    break;
+                }
+                if let Some(job) = job
+                    && !worker.run(job)
+                {
+                    break;
+                }
+            }
+            debug!("Camera worker stopped");
+        });
+
+        Self {
+            name,
+            interrupter,
+            shared,
+            jobs,
+        }
+    }
+
+    pub fn name(&self) -> Option<String> {
+        self.name.clone()
+    }
+
+    /// Queues `job` as `kind` without waiting for it to run, first discarding and cancelling
+    /// whatever a stop of that kind clears. `report` is then given its result once the camera
+    /// has been released.
+    pub fn enqueue<T, J, D>(&self, kind: JobKind, job: J, report: D) -> Result<()>
+    where
+        T: Send + 'static,
+        J: FnOnce(&mut dyn Camera) -> T + Send + 'static,
+        D: FnOnce(T) + Send + 'static,
+    {
+        match kind {
+            JobKind::Stop(axis) => self.flush(Some(axis)),
+            JobKind::Halt => self.flush(None),
+            JobKind::Command | JobKind::Drive(_) | JobKind::Recall => {}
+        }
+
+        let flushes = &self.shared.flushes;
+        let job = Job {
+            kind,
+            flushes: flushes.all.load(Ordering::SeqCst),
+            kind_flushes: flushes
+                .of(kind)
+                .map_or(0, |count| count.load(Ordering::SeqCst)),
+            run: Box::new(move |camera| {
+                let result = job(camera);
+                Box::new(move || report(result))
+            }),
+        };
+
+        let message = if kind.is_stop() {
+            self.shared
+                .stops
+                .lock()
+                .expect("mutex poisoned")
+                .push_back(job);
+            None
+        } else {
+            Some(job)
+        };
+        self.jobs
+            .send(message)
+            .map_err(|_| Error::CameraWorkerStopped)
+    }
+
+    /// Discards the queued drives of `axis` and any queued recall, or the whole queue when it is
+    /// `None`, and cancels the command in flight if it is one of them and the camera allows it.
+    fn flush(&self, axis: Option<Axis>) {
+        let flushes = &self.shared.flushes;
+        let running = self.shared.running.lock().expect("mutex poisoned");
+        match axis {
+            Some(axis) => {
+                flushes.axis(axis).fetch_add(1, Ordering::SeqCst);
+                flushes.recalls.fetch_add(1, Ordering::SeqCst);
+            }
+            None => {
+                flushes.all.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        if interrupts(axis, *running)
+            && let Some(interrupter) = &self.interrupter
+            && let Err(error) = interrupter()
+        {
+            debug!("Interrupting the camera failed: {error}");
+        }
+    }
+
+    /// Queues `job` and returns a receiver for its result, for callers that need a reply.
+    pub fn call<T, F>(&self, job: F) -> Result<Receiver<Result<T>>>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut dyn Camera) -> Result<T> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        self.enqueue(JobKind::Command, job, move |result| {
+            let _ = sender.send(result);
+        })?;
+
+        Ok(receiver)
+    }
+}
+
+impl Drop for CameraWorker {
+    fn drop(&mut self) {
+        // Close the camera now rather than once the queue drains, so its port can be reopened
+        // straight away. Anything still queued is dropped. The UI state's lock is usually held
+        // here, so never wait for a job in flight; the worker closes the camera after it instead.
+        self.shared.closed.store(true, Ordering::SeqCst);
+        if let Ok(mut camera) = self.shared.camera.try_lock() {
+            *camera = None;
+        }
+    }
+}
+
+/// Whether flushing `axis`, or everything when it is `None`, cancels a `running` job. A stop
+/// cancels a drive of its own axis or a preset recall, never the other axis or a plain command.
+fn interrupts(axis: Option<Axis>, running: Option<JobKind>) -> bool {
+    match (axis, running) {
+        (_, None) => false,
+        (None, Some(_)) => true,
+        (Some(axis), Some(kind)) => kind == JobKind::Drive(axis) || kind == JobKind::Recall,
+    }
+}
+
+/// Waits for the result of [`CameraWorker::call`].
+pub fn wait<T>(receiver: Receiver<Result<T>>) -> Result<T> {
+    receiver.recv().map_err(|_| Error::CameraWorkerStopped)?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use test_case::test_case;
+
+    #[cfg(feature = "visca")]
+    #[test]
+    fn test_jobs_run_in_order() {
+        use crate::visca_camera::ViscaCamera;
+
+        let worker = CameraWorker::spawn(Box::new(ViscaCamera::simulated(1)));
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        for index in 0..5 {
+            let order = Arc::clone(&order);
+            worker
+                .enqueue(
+                    JobKind::Command,
+                    |camera| camera.power_on(),
+                    move |result| {
+                        result.unwrap();
+                        order.lock().unwrap().push(index);
+                    },
+                )
+                .unwrap();
+        }
+        let name = wait(worker.call(|camera| Ok(camera.name())).unwrap()).unwrap();
+
+        assert_eq!(*order.lock().unwrap(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(name, worker.name());
+    }
+
+    #[cfg(feature = "visca")]
+    #[test]
+    fn test_call_returns_error() {
+        use crate::visca_camera::ViscaCamera;
+
+        let worker = CameraWorker::spawn(Box::new(ViscaCamera::simulated(1)));
+
+        let result = wait(worker.call(|camera| camera.start_tour()).unwrap());
+        assert!(matches!(result, Err(Error::Unsupported(_))));
+    }
+
+    #[cfg(feature = "visca")]
+    #[test]
+    fn test_drop_does_not_wait_for_the_job_in_flight() {
+        use crate::visca_camera::ViscaCamera;
+
+        let worker = CameraWorker::spawn(Box::new(ViscaCamera::simulated(1)));
+        let (started, starting) = mpsc::channel::<()>();
+        let (release, released) = mpsc::channel::<()>();
+
+        worker
+            .enqueue(
+                JobKind::Command,
+                move |_| {
+                    started.send(()).unwrap();
+                    released.recv().unwrap()
+                },
+                |_| {},
+            )
+            .unwrap();
+        starting.recv().unwrap();
+
+        let shared = Arc::clone(&worker.shared);
+        drop(worker);
+        assert!(shared.closed.load(Ordering::SeqCst));
+        release.send(()).unwrap();
+
+        // The worker closes the camera as soon as the job is done
+        while shared.camera.lock().unwrap().is_some() {
+            thread::yield_now();
+        }
+    }
+
+    #[test_case(Some(Axis::Zoom), None => false; "idle")]
+    #[test_case(Some(Axis::Zoom), Some(JobKind::Drive(Axis::Zoom)) => true; "same axis")]
+    #[test_case(Some(Axis::Zoom), Some(JobKind::Drive(Axis::PanTilt)) => false; "other axis")]
+    #[test_case(Some(Axis::PanTilt), Some(JobKind::Recall) => true; "preset recall")]
+    #[test_case(Some(Axis::PanTilt), Some(JobKind::Command) => false; "other command")]
+    #[test_case(Some(Axis::PanTilt), Some(JobKind::Stop(Axis::Zoom)) => false; "other stop")]
+    #[test_case(None, Some(JobKind::Command) => true; "halt")]
+    fn test_interrupts(axis: Option<Axis>, running: Option<JobKind>) -> bool {
+        interrupts(axis, running)
+    }
+
+    /// Queues `jobs` behind one that holds the worker busy until they are all in, and returns
+    /// the names of the jobs that ran, in order.
+    #[cfg(feature = "visca")]
+    fn run_queued(jobs: &[(JobKind, &'static str)]) -> Vec<&'static str> {
+        use crate::visca_camera::ViscaCamera;
+
+        let worker = CameraWorker::spawn(Box::new(ViscaCamera::simulated(1)));
+        let ran = Arc::new(Mutex::new(Vec::new()));
+        let (release, released) = mpsc::channel::<()>();
+
+        worker
+            .enqueue(JobKind::Command, move |_| released.recv().unwrap(), |_| {})
+            .unwrap();
+        for &(kind, name) in jobs {
+            let ran = Arc::clone(&ran);
+            worker
+                .enqueue(kind, |_| (), move |_| ran.lock().unwrap().push(name))
+                .unwrap();
+        }
+        release.send(()).unwrap();
+
+        wait(worker.call(|_| Ok(())).unwrap()).unwrap();
+        ran.lock().unwrap().clone()
+    }
+
+    #[cfg(feature = "visca")]
+    #[test]
+    fn test_halt_skips_queued_jobs() {
+        let ran = run_queued(&[
+            (JobKind::Recall, "recall"),
+            (JobKind::Drive(Axis::PanTilt), "move"),
+            (JobKind::Stop(Axis::Zoom), "stop zoom"),
+            (JobKind::Halt, "halt"),
+        ]);
+
+        assert_eq!(ran, vec!["halt"]);
+    }
+
+    #[cfg(feature = "visca")]
+    #[test]
+    fn test_stop_jumps_the_queue() {
+        let ran = run_queued(&[
+            (JobKind::Drive(Axis::Zoom), "zoom"),
+            (JobKind::Recall, "recall"),
+            (JobKind::Command, "tally"),
+            (JobKind::Drive(Axis::PanTilt), "move"),
+            (JobKind::Stop(Axis::PanTilt), "stop move"),
+            (JobKind::Drive(Axis::PanTilt), "move again"),
+        ]);
+
+        // The stop runs before everything queued ahead of it, and the recall never runs
+        assert_eq!(ran, vec!["stop move", "zoom", "tally", "move again"]);
+    }
+
+    #[cfg(feature = "visca")]
+    #[test]
+    fn test_stops_keep_earlier_stops() {
+        let ran = run_queued(&[
+            (JobKind::Drive(Axis::Zoom), "zoom"),
+            (JobKind::Drive(Axis::PanTilt), "move"),
+            (JobKind::Stop(Axis::Zoom), "stop zoom"),
+            (JobKind::Stop(Axis::PanTilt), "stop move"),
+        ]);
+
+        assert_eq!(ran, vec!["stop zoom", "stop move"]);
+    }
+}
@@ -0,0 +1,405 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use specta::Type;
+
+use crate::{
+    camera::Protocol,
+    error::{Error, Result},
+};
+
+/// The store file the settings are kept in.
+pub const CONFIG_FILE: &str = "config.json";
+
+/// The layout [`Config`] is saved in. Bump it whenever the layout changes and add a migration
+/// from the version before to [`MIGRATIONS`].
+const CONFIG_VERSION: u32 = 1;
+
+/// Upgrades the store's entries from one version of the layout to the next.
+type Migration = fn(&mut Map<String, Value>) -> Result<()>;
+
+/// `MIGRATIONS[n]` upgrades a version `n` file to version `n + 1`.
+const MIGRATIONS: [Migration; CONFIG_VERSION as usize] = [migrate_single_port];
+
+/// How long, in milliseconds, a move or zoom may go without a keepalive before the watchdog
+/// stops it.
+const DEFAULT_WATCHDOG_TIMEOUT: u32 = 1000;
+
+/// The shots the UI used to hard-code, seeded into a new preset library.
+const DEFAULT_PRESETS: [(u8, &str); 4] = [
+    (1, "Sanctuary"),
+    (2, "Stage"),
+    (3, "Speaker"),
+    (4, "Baptistry"),
+];
+
+/// A named shot in the preset library, kept in the order the operator arranged them.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct PresetConfig {
+    pub number: u8,
+    // Version 0 files may only store speeds, with the names hard-coded in the UI
+    #[serde(default)]
+    pub name: String,
+    /// A CSS color for the preset's button.
+    #[serde(default)]
+    pub color: Option<String>,
+    pub speed: Option<u8>,
+}
+
+/// A labelled auxiliary output, such as the lens heater or a wall light.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct AuxiliaryConfig {
+    pub channel: u8,
+    pub label: String,
+}
+
+/// A named camera connection.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct CameraConfig {
+    pub id: u32,
+    pub name: String,
+    pub protocol: Protocol,
+    pub port: Option<String>,
+    /// The serial number of the USB adapter on `port`, so the camera can be found again when
+    /// the adapter comes back under a different name.
+    #[serde(default)]
+    pub serial_number: Option<String>,
+    pub address: u8,
+}
+
+/// Everything persisted in [`CONFIG_FILE`]. Each field is saved under its own key, alongside
+/// the `version` of the layout.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub version: u32,
+    pub cameras: Vec<CameraConfig>,
+    #[serde(rename = "camera")]
+    pub active_camera: Option<u32>,
+    pub presets: Vec<PresetConfig>,
+    pub auxiliaries: Vec<AuxiliaryConfig>,
+    pub endpoints: Vec<String>,
+    pub watchdog_timeout: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            cameras: Vec::new(),
+            active_camera: None,
+            presets: with_default_presets(&[]),
+            auxiliaries: Vec::new(),
+            endpoints: Vec::new(),
+            watchdog_timeout: DEFAULT_WATCHDOG_TIMEOUT,
+        }
+    }
+}
+
+/// The settings read by [`Config::load`], with what had to be changed to read them.
+#[derive(Debug)]
+pub struct Loaded {
+    pub config: Config,
+    /// Whether the file was saved in an older layout, so the caller can save the new one.
+    pub migrated: bool,
+    /// Settings this build could not use and replaced, to report to the operator.
+    pub warnings: Vec<String>,
+}
+
+impl Config {
+    /// Reads the settings from the store's entries, upgrading files saved by earlier versions.
+    pub fn load(entries: impl IntoIterator<Item = (String, Value)>) -> Result<Loaded> {
+        let mut entries: Map<String, Value> = entries.into_iter().collect();
+
+        // Files from before the layout was versioned have no version at all
+        let version = match entries.get("version") {
+            Some(version) => version
+                .as_u64()
+                .and_then(|version| u32::try_from(version).ok())
+                .ok_or_else(|| Error::InvalidConfig(format!("unknown version {version}")))?,
+            None => 0,
+        };
+        if version > CONFIG_VERSION {
+            return Err(Error::InvalidConfig(format!(
+                "version {version} was saved by a newer release"
+            )));
+        }
+
+        for migrate in &MIGRATIONS[version as usize..] {
+            migrate(&mut entries)?;
+        }
+        entries.insert(String::from("version"), Value::from(CONFIG_VERSION));
+        let warnings = fall_back_protocols(&mut entries);
+
+        let config = serde_json::from_value(Value::Object(entries)).map_err(invalid_config)?;
+        Ok(Loaded {
+            config,
+            migrated: version < CONFIG_VERSION,
+            warnings,
+        })
+    }
+
+    /// The store entries to save, one per field.
+    pub fn entries(&self) -> Map<String, Value> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(entries)) => entries,
+            _ => Map::new(),
+        }
+    }
+}
+
+fn invalid_config(error: serde_json::Error) -> Error {
+    Error::InvalidConfig(error.to_string())
+}
+
+/// Switches cameras saved with a protocol this build does not speak, such as VISCA without the
+/// `visca` feature, to the default protocol, so the rest of the file can still be read.
+fn fall_back_protocols(entries: &mut Map<String, Value>) -> Vec<String> {
+    let Some(Value::Array(cameras)) = entries.get_mut("cameras") else {
+        return Vec::new();
+    };
+
+    let mut warnings = Vec::new();
+    for camera in cameras.iter_mut().filter_map(Value::as_object_mut) {
+        let name = match camera.get("name").and_then(Value::as_str) {
+            Some(name) => String::from(name),
+            None => String::from("A camera"),
+        };
+        let Some(protocol) = camera.get_mut("protocol") else {
+            continue;
+        };
+        if serde_json::from_value::<Protocol>(protocol.clone()).is_ok() {
+            continue;
+        }
+
+        let fallback = Protocol::default();
+        warnings.push(format!(
+            "{name} uses unsupported protocol {protocol}. Using {fallback:?} instead"
+        ));
+        *protocol = serde_json::to_value(fallback).unwrap_or_default();
+    }
+    warnings
+}
+
+/// Version 0 saved a single camera as a `port` and a `protocol`, and its presets could be
+/// missing names.
+fn migrate_single_port(entries: &mut Map<String, Value>) -> Result<()> {
+    let port = entries.remove("port");
+    let protocol = entries.remove("protocol");
+
+    if !entries.contains_key("cameras")
+        && let Some(port) = port
+    {
+        let protocol = match protocol {
+            Some(protocol) => protocol,
+            None => serde_json::to_value(Protocol::default()).map_err(invalid_config)?,
+        };
+        // An unknown protocol is kept as it was for `fall_back_protocols` to report
+        let address = *serde_json::from_value::<Protocol>(protocol.clone())
+            .unwrap_or_default()
+            .addresses()
+            .start();
+        let port: Option<String> = serde_json::from_value(port).map_err(invalid_config)?;
+
+        entries.insert(
+            String::from("cameras"),
+            serde_json::json!([{
+                "id": 1,
+                "name": default_camera_name(1),
+                "protocol": protocol,
+                "port": port,
+                "serial_number": null,
+                "address": address,
+            }]),
+        );
+        entries.insert(String::from("camera"), Value::from(1));
+    }
+
+    if let Some(presets) = entries.get_mut("presets") {
+        let saved: Vec<PresetConfig> =
+            serde_json::from_value(presets.clone()).map_err(invalid_config)?;
+        if saved.iter().any(|preset| preset.name.is_empty()) {
+            *presets =
+                serde_json::to_value(with_default_presets(&saved)).map_err(invalid_config)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Fills in names for presets saved before the library had them, seeding the shots the UI used
+/// to hard-code.
+fn with_default_presets(saved: &[PresetConfig]) -> Vec<PresetConfig> {
+    let mut presets: Vec<PresetConfig> = DEFAULT_PRESETS
+        .iter()
+        .map(|(number, name)| PresetConfig {
+            number: *number,
+            name: String::from(*name),
+            color: None,
+            speed: None,
+        })
+        .collect();
+
+    for config in saved {
+        match presets
+            .iter_mut()
+            .find(|preset| preset.number == config.number)
+        {
+            Some(preset) if config.name.is_empty() => preset.speed = config.speed,
+            Some(preset) => *preset = config.clone(),
+            None => presets.push(PresetConfig {
+                name: preset_name(config.number, &config.name),
+                ..config.clone()
+            }),
+        }
+    }
+
+    presets
+}
+
+/// Falls back to a numbered name when `name` is blank.
+pub fn preset_name(preset: u8, name: &str) -> String {
+    match name.trim() {
+        "" => default_preset_name(preset),
+        name => String::from(name),
+    }
+}
+
+pub fn default_preset_name(preset: u8) -> String {
+    format!("Preset {preset}")
+}
+
+/// Falls back to a numbered name when `name` is blank.
+pub fn camera_name(id: u32, name: &str) -> String {
+    match name.trim() {
+        "" => default_camera_name(id),
+        name => String::from(name),
+    }
+}
+
+fn default_camera_name(id: u32) -> String {
+    format!("Camera {id}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+    use test_case::test_case;
+
+    fn load(entries: Value) -> Result<(Config, bool)> {
+        let Value::Object(entries) = entries else {
+            panic!("entries must be an object");
+        };
+        let loaded = Config::load(entries)?;
+        assert!(loaded.warnings.is_empty(), "{:?}", loaded.warnings);
+        Ok((loaded.config, loaded.migrated))
+    }
+
+    #[test]
+    fn test_empty_file_uses_defaults() -> Result<()> {
+        let (config, migrated) = load(json!({}))?;
+
+        assert!(migrated);
+        assert!(config.cameras.is_empty());
+        assert_eq!(config.presets.len(), DEFAULT_PRESETS.len());
+        assert_eq!(config.watchdog_timeout, DEFAULT_WATCHDOG_TIMEOUT);
+        Ok(())
+    }
+
+    #[test]
+    fn test_single_port_is_migrated() -> Result<()> {
+        let (config, migrated) = load(json!({ "port": "/dev/ttyUSB0", "protocol": "PelcoP" }))?;
+
+        assert!(migrated);
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.active_camera, Some(1));
+        let [camera] = config.cameras.as_slice() else {
+            panic!("expected one camera, got {:?}", config.cameras);
+        };
+        assert_eq!(camera.name, "Camera 1");
+        assert_eq!(camera.port.as_deref(), Some("/dev/ttyUSB0"));
+        assert_eq!(camera.protocol, Protocol::PelcoP);
+        assert_eq!(camera.address, 0);
+
+        let entries = config.entries();
+        assert!(!entries.contains_key("port"));
+        assert_eq!(entries["version"], json!(CONFIG_VERSION));
+        Ok(())
+    }
+
+    #[test]
+    fn test_nameless_presets_are_migrated() -> Result<()> {
+        let (config, _) = load(json!({
+            "presets": [{ "number": 2, "speed": 5 }, { "number": 7, "speed": null }]
+        }))?;
+
+        let presets: Vec<(u8, &str, Option<u8>)> = config
+            .presets
+            .iter()
+            .map(|preset| (preset.number, preset.name.as_str(), preset.speed))
+            .collect();
+        assert_eq!(
+            presets,
+            vec![
+                (1, "Sanctuary", None),
+                (2, "Stage", Some(5)),
+                (3, "Speaker", None),
+                (4, "Baptistry", None),
+                (7, "Preset 7", None),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_current_file_is_not_migrated() -> Result<()> {
+        let (config, migrated) = load(json!({
+            "version": CONFIG_VERSION,
+            "presets": [{ "number": 9, "name": "Choir", "speed": null }],
+            "watchdog_timeout": 500
+        }))?;
+
+        assert!(!migrated);
+        assert_eq!(config.presets.len(), 1);
+        assert_eq!(config.watchdog_timeout, 500);
+        Ok(())
+    }
+
+    #[test_case(json!({ "port": "COM3", "protocol": "Sony" }); "single port")]
+    #[test_case(json!({
+        "version": CONFIG_VERSION,
+        "cameras": [
+            { "id": 1, "name": "Pulpit", "protocol": "PelcoP", "port": "COM3", "address": 0 },
+            { "id": 2, "name": "Balcony", "protocol": "Sony", "port": "COM4", "address": 2 }
+        ]
+    }); "one of several cameras")]
+    fn test_unknown_protocol_falls_back(entries: Value) -> Result<()> {
+        let Value::Object(entries) = entries else {
+            panic!("entries must be an object");
+        };
+        let loaded = Config::load(entries)?;
+
+        let camera = loaded.config.cameras.last().expect("the camera is kept");
+        assert_eq!(camera.protocol, Protocol::default());
+        assert!(camera.port.is_some());
+        assert!(
+            loaded.config.cameras[..loaded.config.cameras.len() - 1]
+                .iter()
+                .all(|camera| camera.protocol == Protocol::PelcoP)
+        );
+        let [warning] = loaded.warnings.as_slice() else {
+            panic!("expected one warning, got {:?}", loaded.warnings);
+        };
+        assert!(warning.contains("\"Sony\""), "{warning}");
+        Ok(())
+    }
+
+    #[test_case(json!({ "port": 5 }); "port is not a string")]
+    #[test_case(json!({ "version": CONFIG_VERSION, "cameras": {} }); "cameras is not a list")]
+    #[test_case(json!({ "version": CONFIG_VERSION + 1 }); "newer version")]
+    #[test_case(json!({ "version": "one" }); "version is not a number")]
+    fn test_unreadable_config(entries: Value) {
+        assert!(matches!(load(entries), Err(Error::InvalidConfig(_))));
+    }
+}
@@ -1,3 +1,5 @@
+use std::io::ErrorKind;
+
 use serde::Serialize;
 use specta::Type;
 use thiserror::Error as ThisError;
@@ -8,6 +10,54 @@ pub enum Error {
     #[error("No port set")]
     NoPortSet,
 
+    #[error("No camera selected")]
+    NoCameraSelected,
+
+    #[error("Invalid camera: {0}")]
+    InvalidCamera(u32),
+
+    #[error("Invalid address: {0}")]
+    InvalidAddress(u8),
+
+    #[error("Invalid hex: {0}")]
+    InvalidHex(String),
+
+    #[error("Invalid endpoint: {0}")]
+    InvalidEndpoint(String),
+
+    #[error("Invalid auxiliary channel: {0}")]
+    InvalidAuxiliary(u8),
+
+    #[error("Invalid response: {0}")]
+    InvalidResponse(String),
+
+    #[error("Checksum mismatch: expected {expected:02X}, received {received:02X}")]
+    InvalidChecksum { expected: u8, received: u8 },
+
+    #[error("No response from the camera")]
+    NoResponse,
+
+    #[error("Invalid preset: {0}")]
+    InvalidPreset(u8),
+
+    #[error("Preset {0} already exists")]
+    DuplicatePreset(u8),
+
+    #[error("Invalid speed: {0}")]
+    InvalidSpeed(u8),
+
+    #[error("Invalid watchdog timeout: {0} ms")]
+    InvalidWatchdogTimeout(u32),
+
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+
+    #[error("{0} is not supported by this camera")]
+    Unsupported(String),
+
+    #[error("Camera worker stopped")]
+    CameraWorkerStopped,
+
     #[error("Tauri error: {0}")]
     Tauri(
         #[serde(skip)]
@@ -42,6 +92,61 @@ pub enum Error {
         #[from]
         pelcodrs::Error,
     ),
+
+    #[cfg(feature = "visca")]
+    #[error("VISCA error: {0}")]
+    Visca(
+        #[serde(skip)]
+        #[from]
+        crate::visca::ViscaError,
+    ),
+}
+
+impl Error {
+    /// Whether the camera's port went away, such as a USB-serial adapter being unplugged, as
+    /// opposed to the camera rejecting or not answering a command.
+    pub fn is_disconnect(&self) -> bool {
+        match self {
+            Error::CameraWorkerStopped => true,
+            Error::Io(error) => is_disconnect_kind(error.kind()),
+            Error::SerialPort(error) => is_serial_disconnect(error),
+            #[cfg(feature = "visca")]
+            Error::Visca(crate::visca::ViscaError::Io(error)) => is_disconnect_kind(error.kind()),
+            #[cfg(feature = "visca")]
+            Error::Visca(crate::visca::ViscaError::SerialPort(error)) => {
+                is_serial_disconnect(error)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether a command was abandoned because a stop cut in ahead of it.
+    pub fn is_canceled(&self) -> bool {
+        match self {
+            #[cfg(feature = "visca")]
+            Error::Visca(crate::visca::ViscaError::CommandCanceled) => true,
+            _ => false,
+        }
+    }
+}
+
+fn is_disconnect_kind(kind: ErrorKind) -> bool {
+    !matches!(
+        kind,
+        ErrorKind::TimedOut
+            | ErrorKind::WouldBlock
+            | ErrorKind::Interrupted
+            | ErrorKind::InvalidInput
+            | ErrorKind::InvalidData
+    )
+}
+
+fn is_serial_disconnect(error: &serialport::Error) -> bool {
+    match error.kind() {
+        serialport::ErrorKind::NoDevice => true,
+        serialport::ErrorKind::Io(kind) => is_disconnect_kind(kind),
+        _ => false,
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -5,18 +5,29 @@ extern crate log;
 extern crate pretty_env_logger;
 
 mod camera;
+mod camera_worker;
+mod config;
 mod error;
+mod monitor;
 mod pelco_camera;
+mod pelco_p_camera;
+mod status;
+mod transport;
 mod ui_state;
 #[cfg(feature = "visca")]
 mod visca;
+#[cfg(feature = "visca")]
+mod visca_camera;
+mod watchdog;
 
-use std::sync::Mutex;
+use std::{sync::Mutex, time::Duration};
 
 use crate::error::Result;
 
-use camera::Direction;
+use camera::{Direction, PortInfo, Protocol, RawReply, ResetKind, Speed};
+use config::PresetConfig;
 use log::debug;
+use status::Status;
 use tauri::{
     Manager, WindowEvent,
     menu::{MenuBuilder, MenuItemBuilder, SubmenuBuilder},
@@ -24,7 +35,9 @@ use tauri::{
 use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
 use tauri_plugin_updater::UpdaterExt;
 use tauri_plugin_window_state::StateFlags;
-use ui_state::{UIState, UIStateEvent, with_ui_state, with_ui_state_status};
+use ui_state::{UIState, UIStateEvent, with_camera_status, with_ui_state};
+
+const EXIT_STOP_TIMEOUT: Duration = Duration::from_secs(1);
 
 fn open_settings_window(app_handle: &tauri::AppHandle) -> Result<()> {
     if let Some(window) = app_handle.get_webview_window("settings") {
@@ -46,7 +59,7 @@ fn open_settings_window(app_handle: &tauri::AppHandle) -> Result<()> {
 #[tauri::command]
 #[specta::specta]
 async fn open_settings(app_handle: tauri::AppHandle) -> Result<()> {
-    with_ui_state(&app_handle, |ui| ui.refresh_ports());
+    with_ui_state(&app_handle, Some("open_settings"), |ui| ui.refresh_ports());
     open_settings_window(&app_handle)
 }
 
@@ -56,12 +69,101 @@ fn get_state(app_handle: tauri::AppHandle) -> Result<UIStateEvent> {
     UIStateEvent::try_from(&app_handle)
 }
 
+#[tauri::command]
+#[specta::specta]
+fn add_camera(
+    app_handle: tauri::AppHandle,
+    name: &str,
+    protocol: Protocol,
+    port_name: Option<&str>,
+    address: u8,
+) {
+    debug!("Add camera: {name} {protocol:?} {port_name:?} {address}");
+
+    with_ui_state(&app_handle, Some("add_camera"), |ui| {
+        ui.add_camera(&app_handle, name, protocol, port_name, address)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn remove_camera(app_handle: tauri::AppHandle, id: u32) {
+    debug!("Remove camera: {id}");
+
+    with_ui_state(&app_handle, Some("remove_camera"), |ui| {
+        ui.remove_camera(&app_handle, id)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn rename_camera(app_handle: tauri::AppHandle, id: u32, name: &str) {
+    debug!("Rename camera: {id} {name}");
+
+    with_ui_state(&app_handle, Some("rename_camera"), |ui| {
+        ui.rename_camera(&app_handle, id, name)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn select_camera(app_handle: tauri::AppHandle, id: u32) {
+    debug!("Select camera: {id}");
+
+    with_ui_state(&app_handle, Some("select_camera"), |ui| {
+        ui.select_camera(&app_handle, id)
+    })
+}
+
 #[tauri::command]
 #[specta::specta]
 fn set_port(app_handle: tauri::AppHandle, port_name: Option<&str>) {
     debug!("Port name: {port_name:?}");
 
-    with_ui_state(&app_handle, |ui| ui.set_camera_port(&app_handle, port_name))
+    with_ui_state(&app_handle, Some("set_port"), |ui| {
+        ui.set_camera_port(&app_handle, port_name)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn add_endpoint(app_handle: tauri::AppHandle, endpoint: &str) {
+    debug!("Add endpoint: {endpoint}");
+
+    with_ui_state(&app_handle, Some("add_endpoint"), |ui| {
+        ui.add_endpoint(&app_handle, endpoint)?;
+        ui.set_camera_port(&app_handle, Some(endpoint.trim()))
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn remove_endpoint(app_handle: tauri::AppHandle, endpoint: &str) {
+    debug!("Remove endpoint: {endpoint}");
+
+    with_ui_state(&app_handle, Some("remove_endpoint"), |ui| {
+        ui.remove_endpoint(&app_handle, endpoint)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn set_protocol(app_handle: tauri::AppHandle, protocol: Protocol) {
+    debug!("Protocol: {protocol:?}");
+
+    with_ui_state(&app_handle, Some("set_protocol"), |ui| {
+        ui.set_protocol(&app_handle, protocol)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn set_address(app_handle: tauri::AppHandle, address: u8) {
+    debug!("Address: {address}");
+
+    with_ui_state(&app_handle, Some("set_address"), |ui| {
+        ui.set_camera_address(&app_handle, address)
+    })
 }
 
 #[tauri::command]
@@ -69,65 +171,249 @@ fn set_port(app_handle: tauri::AppHandle, port_name: Option<&str>) {
 fn camera_power(app_handle: tauri::AppHandle, power: bool) {
     debug!("Power: {:?}", power);
 
-    with_ui_state_status(
-        &app_handle,
-        if power { "Power on" } else { "Power off" },
-        |ui| {
-            let camera = ui.camera()?;
-
-            if power {
-                camera.power_on()
-            } else {
-                camera.power_off()
-            }
-        },
-    )
+    with_ui_state(&app_handle, Some("camera_power"), |ui| {
+        ui.camera_power(&app_handle, power)
+    })
 }
 
 #[tauri::command]
 #[specta::specta]
 fn autofocus(app_handle: tauri::AppHandle, autofocus: bool) {
-    with_ui_state_status(
+    with_camera_status(
         &app_handle,
+        "autofocus",
         if autofocus {
             "Autofocus on"
         } else {
             "Autofocus off"
         },
-        |ui| ui.camera()?.autofocus(autofocus),
+        move |camera| camera.autofocus(autofocus),
     )
 }
 
 #[tauri::command]
 #[specta::specta]
-fn go_to_preset(app_handle: tauri::AppHandle, preset: u8, name: &str) {
+fn go_to_preset(app_handle: tauri::AppHandle, preset: u8) {
     debug!("Go To Preset: {}", preset);
 
-    with_ui_state_status(&app_handle, name, |ui| ui.camera()?.go_to_preset(preset));
+    with_ui_state(&app_handle, Some("go_to_preset"), |ui| {
+        ui.go_to_preset(&app_handle, preset)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+fn set_preset_speed(app_handle: tauri::AppHandle, preset: u8, speed: Option<u8>) {
+    debug!("Set Preset Speed: {} {:?}", preset, speed);
+
+    with_ui_state(&app_handle, Some("set_preset_speed"), |ui| {
+        ui.set_preset_speed(&app_handle, preset, speed)
+    });
 }
 
 #[tauri::command]
 #[specta::specta]
-fn set_preset(app_handle: tauri::AppHandle, preset: u8, name: &str) {
+fn set_preset(app_handle: tauri::AppHandle, preset: u8) {
     debug!("Set Preset: {}", preset);
 
-    let status = format!("Set {name}");
-    with_ui_state_status(&app_handle, &status, |ui| ui.camera()?.set_preset(preset));
+    with_ui_state(&app_handle, Some("set_preset"), |ui| {
+        ui.set_preset(&app_handle, preset)
+    });
 }
 
 #[tauri::command]
 #[specta::specta]
-fn move_camera(app_handle: tauri::AppHandle, direction: &str) {
-    debug!("Direction: {}", direction);
+fn list_presets(app_handle: tauri::AppHandle) -> Vec<PresetConfig> {
+    let state = app_handle.state::<Mutex<UIState>>();
+    let state = state.lock().expect("mutext poisoned");
 
-    let status = format!("Moving {direction}");
-    with_ui_state_status(&app_handle, &status, |ui| {
-        ui.camera()?.pan_tilt(match direction {
-            "left" => Direction::Left,
-            "up" => Direction::Up,
-            "right" => Direction::Right,
-            &_ => Direction::Down,
-        })
+    state.presets().to_vec()
+}
+
+#[tauri::command]
+#[specta::specta]
+fn create_preset(app_handle: tauri::AppHandle, preset: u8, name: &str, color: Option<&str>) {
+    debug!("Create Preset: {} {} {:?}", preset, name, color);
+
+    with_ui_state(&app_handle, Some("create_preset"), |ui| {
+        ui.create_preset(&app_handle, preset, name, color)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+fn rename_preset(app_handle: tauri::AppHandle, preset: u8, name: &str) {
+    debug!("Rename Preset: {} {}", preset, name);
+
+    with_ui_state(&app_handle, Some("rename_preset"), |ui| {
+        ui.rename_preset(&app_handle, preset, name)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+fn set_preset_color(app_handle: tauri::AppHandle, preset: u8, color: Option<&str>) {
+    debug!("Set Preset Color: {} {:?}", preset, color);
+
+    with_ui_state(&app_handle, Some("set_preset_color"), |ui| {
+        ui.set_preset_color(&app_handle, preset, color)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+fn reorder_presets(app_handle: tauri::AppHandle, order: Vec<u8>) {
+    debug!("Reorder Presets: {:?}", order);
+
+    with_ui_state(&app_handle, Some("reorder_presets"), |ui| {
+        ui.reorder_presets(&app_handle, &order)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+fn delete_preset(app_handle: tauri::AppHandle, preset: u8) {
+    debug!("Delete Preset: {}", preset);
+
+    with_ui_state(&app_handle, Some("delete_preset"), |ui| {
+        ui.delete_preset(&app_handle, preset)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+fn set_tally(app_handle: tauri::AppHandle, tally: bool) {
+    debug!("Tally: {:?}", tally);
+
+    with_ui_state(&app_handle, Some("set_tally"), |ui| {
+        ui.set_tally(&app_handle, tally)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+fn auxiliary(app_handle: tauri::AppHandle, channel: u8, state: bool) {
+    debug!("Auxiliary: {} {:?}", channel, state);
+
+    with_ui_state(&app_handle, Some("auxiliary"), |ui| {
+        ui.auxiliary(&app_handle, channel, state)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+fn set_auxiliary_label(app_handle: tauri::AppHandle, channel: u8, label: &str) {
+    debug!("Set Auxiliary Label: {} {}", channel, label);
+
+    with_ui_state(&app_handle, Some("set_auxiliary_label"), |ui| {
+        ui.set_auxiliary_label(&app_handle, channel, label)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+fn start_pattern(app_handle: tauri::AppHandle, pattern: u8) {
+    debug!("Start Pattern: {}", pattern);
+
+    let status = format!("Recording pattern {pattern}");
+    with_camera_status(&app_handle, "start_pattern", &status, move |camera| {
+        camera.start_pattern(pattern)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+fn stop_pattern(app_handle: tauri::AppHandle, pattern: u8) {
+    debug!("Stop Pattern: {}", pattern);
+
+    let status = format!("Recorded pattern {pattern}");
+    with_camera_status(&app_handle, "stop_pattern", &status, move |camera| {
+        camera.stop_pattern(pattern)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+fn run_pattern(app_handle: tauri::AppHandle, pattern: u8) {
+    debug!("Run Pattern: {}", pattern);
+
+    let status = format!("Running pattern {pattern}");
+    with_camera_status(&app_handle, "run_pattern", &status, move |camera| {
+        camera.run_pattern(pattern)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+fn start_tour(app_handle: tauri::AppHandle) {
+    debug!("Start Tour");
+
+    with_camera_status(&app_handle, "start_tour", "Touring presets", |camera| {
+        camera.start_tour()
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+fn stop_tour(app_handle: tauri::AppHandle) {
+    debug!("Stop Tour");
+
+    // Any movement ends a tour or a running pattern
+    with_camera_status(&app_handle, "stop_tour", "Stopped", |camera| camera.stop());
+}
+
+// This command MUST be async so the confirmation dialog does not block the main thread
+#[tauri::command]
+#[specta::specta]
+async fn reset_camera(app_handle: tauri::AppHandle, kind: ResetKind) {
+    debug!("Reset: {kind:?}");
+
+    let (message, status) = match kind {
+        ResetKind::Lens => (
+            "The camera will recalibrate its zoom and focus.",
+            "Lens reset",
+        ),
+        ResetKind::Camera => (
+            "The camera will restart and won't respond until it comes back.",
+            "Camera reset",
+        ),
+        ResetKind::FactoryDefaults => (
+            "The camera will erase its presets and settings. This cannot be undone.",
+            "Reset to factory defaults",
+        ),
+    };
+
+    let confirmed = app_handle
+        .dialog()
+        .message(format!("{message}\n\nAre you sure you want to continue?"))
+        .title("Reset Camera")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancel)
+        .blocking_show();
+
+    if confirmed {
+        with_camera_status(&app_handle, "reset_camera", status, move |camera| {
+            camera.reset(kind)
+        });
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+fn move_camera(app_handle: tauri::AppHandle, direction: &str, speed: Speed) {
+    debug!("Direction: {} {:?}", direction, speed);
+
+    with_ui_state(&app_handle, Some("move_camera"), |ui| {
+        ui.move_camera(
+            &app_handle,
+            match direction {
+                "left" => Direction::Left,
+                "up" => Direction::Up,
+                "right" => Direction::Right,
+                &_ => Direction::Down,
+            },
+            speed,
+        )
     })
 }
 
@@ -136,23 +422,72 @@ fn move_camera(app_handle: tauri::AppHandle, direction: &str) {
 fn stop_move(app_handle: tauri::AppHandle) {
     debug!("Stop Move");
 
-    with_ui_state_status(&app_handle, "Done moving", |ui| ui.camera()?.stop())
+    with_ui_state(&app_handle, Some("stop_move"), |ui| {
+        ui.stop_move(&app_handle)
+    })
 }
 
 #[tauri::command]
 #[specta::specta]
-fn zoom(app_handle: tauri::AppHandle, direction: &str) {
-    debug!("Zoom: {}", direction);
+fn open_camera_menu(app_handle: tauri::AppHandle) {
+    debug!("Open Camera Menu");
 
-    let status = format!("Zooming {direction}");
-    with_ui_state_status(&app_handle, &status, |ui| {
-        let camera = ui.camera()?;
+    with_ui_state(&app_handle, Some("open_camera_menu"), |ui| {
+        ui.open_menu(&app_handle)
+    });
+}
 
-        if direction == "in" {
-            camera.zoom_in()
-        } else {
-            camera.zoom_out()
-        }
+#[tauri::command]
+#[specta::specta]
+fn close_camera_menu(app_handle: tauri::AppHandle) {
+    debug!("Close Camera Menu");
+
+    with_ui_state(&app_handle, Some("close_camera_menu"), |ui| {
+        ui.close_menu(&app_handle)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+fn camera_menu_enter(app_handle: tauri::AppHandle) {
+    debug!("Camera Menu Enter");
+
+    with_camera_status(
+        &app_handle,
+        "camera_menu_enter",
+        "Menu item selected",
+        |camera| camera.menu_enter(),
+    );
+}
+
+#[tauri::command]
+#[specta::specta]
+fn camera_menu_back(app_handle: tauri::AppHandle) {
+    debug!("Camera Menu Back");
+
+    with_camera_status(&app_handle, "camera_menu_back", "Menu back", |camera| {
+        camera.menu_back()
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+fn zoom(app_handle: tauri::AppHandle, direction: &str, speed: Speed) {
+    debug!("Zoom: {} {:?}", direction, speed);
+
+    with_ui_state(&app_handle, Some("zoom"), |ui| {
+        ui.zoom(&app_handle, direction == "in", speed)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+fn set_focus_speed(app_handle: tauri::AppHandle, speed: Speed) {
+    debug!("Focus Speed: {:?}", speed);
+
+    let status = format!("Focus speed {speed:?}");
+    with_camera_status(&app_handle, "set_focus_speed", &status, move |camera| {
+        camera.set_focus_speed(speed)
     });
 }
 
@@ -161,16 +496,72 @@ fn zoom(app_handle: tauri::AppHandle, direction: &str) {
 fn stop_zoom(app_handle: tauri::AppHandle) {
     debug!("Stop Zoom");
 
-    with_ui_state_status(&app_handle, "Done zooming", |ui| ui.camera()?.stop());
+    with_ui_state(&app_handle, Some("stop_zoom"), |ui| {
+        ui.stop_zoom(&app_handle)
+    });
+}
+
+/// Sent repeatedly while a move or zoom button is held, so the watchdog knows not to stop it.
+#[tauri::command]
+#[specta::specta]
+fn keep_alive(app_handle: tauri::AppHandle) {
+    let state = app_handle.state::<Mutex<UIState>>();
+    let mut state = state.lock().expect("mutext poisoned");
+
+    state.keep_alive();
 }
 
 #[tauri::command]
 #[specta::specta]
-fn get_ports() -> Result<Vec<String>> {
-    Ok(serialport::available_ports()?
-        .into_iter()
-        .map(|port| port.port_name)
-        .collect())
+fn set_watchdog_timeout(app_handle: tauri::AppHandle, timeout: u32) {
+    debug!("Watchdog Timeout: {timeout}");
+
+    with_ui_state(&app_handle, Some("set_watchdog_timeout"), |ui| {
+        ui.set_watchdog_timeout(&app_handle, timeout)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+fn emergency_stop(app_handle: tauri::AppHandle) {
+    debug!("Emergency Stop");
+
+    with_ui_state(&app_handle, Some("emergency_stop"), |ui| {
+        ui.emergency_stop(&app_handle)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+fn send_raw(app_handle: tauri::AppHandle, bytes: &str) -> Result<RawReply> {
+    debug!("Send raw: {}", bytes);
+
+    let bytes = camera::parse_hex(bytes)?;
+
+    let reply = {
+        let state = app_handle.state::<Mutex<UIState>>();
+        let state = state.lock().expect("mutext poisoned");
+
+        state.call(move |camera| camera.send_raw(&bytes))?
+    };
+
+    // Wait for the reply without holding the lock, so other commands aren't held up
+    camera_worker::wait(reply)
+}
+
+#[tauri::command]
+#[specta::specta]
+fn get_status_history(app_handle: tauri::AppHandle) -> Vec<Status> {
+    let state = app_handle.state::<Mutex<UIState>>();
+    let state = state.lock().expect("mutext poisoned");
+
+    state.status_history()
+}
+
+#[tauri::command]
+#[specta::specta]
+fn get_ports() -> Result<Vec<PortInfo>> {
+    camera::available_ports()
 }
 
 fn main() {
@@ -189,15 +580,49 @@ fn main() {
         .commands(tauri_specta::collect_commands![
             open_settings,
             get_state,
+            add_camera,
+            remove_camera,
+            rename_camera,
+            select_camera,
             set_port,
+            add_endpoint,
+            remove_endpoint,
+            set_protocol,
+            set_address,
             camera_power,
             autofocus,
             go_to_preset,
             set_preset,
+            list_presets,
+            create_preset,
+            rename_preset,
+            set_preset_color,
+            reorder_presets,
+            delete_preset,
+            set_preset_speed,
+            set_tally,
+            auxiliary,
+            set_auxiliary_label,
+            start_pattern,
+            stop_pattern,
+            run_pattern,
+            start_tour,
+            stop_tour,
+            reset_camera,
             move_camera,
             stop_move,
             zoom,
+            set_focus_speed,
             stop_zoom,
+            emergency_stop,
+            keep_alive,
+            set_watchdog_timeout,
+            open_camera_menu,
+            close_camera_menu,
+            camera_menu_enter,
+            camera_menu_back,
+            send_raw,
+            get_status_history,
             get_ports,
         ])
         .events(tauri_specta::collect_events![UIStateEvent])
@@ -256,16 +681,41 @@ fn main() {
         .manage(Mutex::new(UIState::default()))
         .invoke_handler(specta_builder.invoke_handler())
         .on_window_event(|window, event| {
+            // Moves are driven by holding a button in the main window, so one can't still be
+            // wanted once it is no longer in front. Other windows, like settings, coming and
+            // going leave the camera alone.
+            if window.label() != "main" {
+                return;
+            }
+
+            let reason = match event {
+                WindowEvent::Focused(false) => "Stopped: the window lost focus",
+                WindowEvent::Destroyed => "Stopped: the window closed",
+                _ => return,
+            };
+
+            let stopped = {
+                let state = window.state::<Mutex<UIState>>();
+                let mut ui = state.lock().expect("mutext poisoned");
+
+                ui.halt(window.app_handle(), reason)
+            };
+
             if let WindowEvent::Destroyed = event {
-                if window.label() == "main" {
-                    std::process::exit(0);
+                // The camera's worker dies with the process, so give the stop a moment to go out
+                // first
+                if let Some(stopped) = stopped {
+                    let _ = stopped.recv_timeout(EXIT_STOP_TIMEOUT);
                 }
+                std::process::exit(0);
             }
         })
         .setup(move |app| {
             specta_builder.mount_events(app);
 
-            with_ui_state(app.app_handle(), |ui| ui.initialize(app.handle()));
+            with_ui_state(app.app_handle(), None, |ui| ui.initialize(app.handle()));
+            monitor::spawn(app.app_handle().clone());
+            watchdog::spawn(app.app_handle().clone());
 
             #[cfg(target_os = "macos")]
             {
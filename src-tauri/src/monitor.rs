@@ -0,0 +1,215 @@
+use std::{
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::debug;
+use tauri::Manager;
+use tauri_specta::Event;
+
+use crate::{
+    camera::{self, PortInfo},
+    ui_state::{UIState, UIStateEvent},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// What one check decided about the saved port.
+#[derive(Debug, PartialEq)]
+enum Transition {
+    Unchanged,
+    /// The connected port has gone away.
+    Unplugged,
+    /// The port is present and due another reconnect attempt.
+    Reconnect,
+}
+
+/// Tracks when to next try reopening the saved port, backing off while attempts keep failing.
+#[derive(Debug)]
+struct Reconnects {
+    backoff: Duration,
+    next_attempt: Instant,
+}
+
+impl Reconnects {
+    fn new(now: Instant) -> Self {
+        Self {
+            backoff: MIN_BACKOFF,
+            next_attempt: now,
+        }
+    }
+
+    /// Decides what to do about `port`, given the ports currently available.
+    fn check(
+        &mut self,
+        port: &str,
+        available: &[PortInfo],
+        connected: bool,
+        now: Instant,
+    ) -> Transition {
+        // Endpoints never show up in the port list, so just keep trying them
+        let present =
+            camera::is_network_address(port) || available.iter().any(|info| info.name == port);
+
+        if connected {
+            self.backoff = MIN_BACKOFF;
+
+            if present {
+                Transition::Unchanged
+            } else {
+                Transition::Unplugged
+            }
+        } else if present && now >= self.next_attempt {
+            Transition::Reconnect
+        } else {
+            Transition::Unchanged
+        }
+    }
+
+    fn succeeded(&mut self) {
+        self.backoff = MIN_BACKOFF;
+    }
+
+    /// Puts off the next attempt, waiting twice as long each time up to [`MAX_BACKOFF`].
+    fn failed(&mut self, now: Instant) {
+        self.next_attempt = now + self.backoff;
+        self.backoff = (self.backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Watches for serial ports coming and going, closing the camera when its adapter is unplugged
+/// and reopening it once it is back.
+pub fn spawn(app_handle: tauri::AppHandle) {
+    thread::spawn(move || {
+        let mut reconnects = Reconnects::new(Instant::now());
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            // Enumerate before taking the lock, as it can be slow
+            let available = match camera::available_ports() {
+                Ok(available) => available,
+                Err(error) => {
+                    debug!("Listing ports failed: {error}");
+                    continue;
+                }
+            };
+
+            let state = app_handle.state::<Mutex<UIState>>();
+            let mut ui = state.lock().expect("mutext poisoned");
+
+            let mut changed = ui.set_available_ports(available.clone());
+            changed |= ui.follow_adapter(&app_handle).unwrap_or_default();
+
+            let mut reopen = None;
+            if let Some(port) = ui.saved_port() {
+                match reconnects.check(&port, &available, ui.is_connected(), Instant::now()) {
+                    Transition::Unchanged => {}
+                    Transition::Unplugged => {
+                        let _ = ui.mark_disconnected(&format!("{port} was unplugged"));
+                        changed = true;
+                    }
+                    Transition::Reconnect => reopen = ui.reopening().map(|reopen| (port, reopen)),
+                }
+            }
+
+            if changed {
+                let _ = UIStateEvent::new(&mut ui).emit(&app_handle);
+            }
+            drop(ui);
+
+            let Some((port, reopen)) = reopen else {
+                continue;
+            };
+
+            // Opening an unreachable endpoint takes seconds, so do it without the lock held
+            let opened = reopen.open();
+            let mut ui = state.lock().expect("mutext poisoned");
+            match opened.and_then(|camera| ui.reconnect(&app_handle, &reopen, camera)) {
+                Ok(true) => {
+                    reconnects.succeeded();
+                    let _ = UIStateEvent::new(&mut ui).emit(&app_handle);
+                }
+                // The camera was changed meanwhile, which opened the new one
+                Ok(false) => {}
+                Err(error) => {
+                    debug!("Reconnecting to {port} failed: {error}");
+                    reconnects.failed(Instant::now());
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PORT: &str = "/dev/ttyUSB0";
+
+    #[test]
+    fn test_unplugged_while_connected() {
+        let now = Instant::now();
+        let mut reconnects = Reconnects::new(now);
+
+        assert_eq!(
+            reconnects.check(PORT, &[PortInfo::named(PORT)], true, now),
+            Transition::Unchanged
+        );
+        assert_eq!(
+            reconnects.check(PORT, &[], true, now),
+            Transition::Unplugged
+        );
+    }
+
+    #[test]
+    fn test_reconnects_once_present() {
+        let now = Instant::now();
+        let mut reconnects = Reconnects::new(now);
+
+        assert_eq!(
+            reconnects.check(PORT, &[], false, now),
+            Transition::Unchanged
+        );
+        assert_eq!(
+            reconnects.check(PORT, &[PortInfo::named(PORT)], false, now),
+            Transition::Reconnect
+        );
+    }
+
+    #[test]
+    fn test_failed_reconnects_back_off() {
+        let start = Instant::now();
+        let available = [PortInfo::named(PORT)];
+        let mut reconnects = Reconnects::new(start);
+
+        reconnects.failed(start);
+        assert_eq!(reconnects.backoff, MIN_BACKOFF * 2);
+        assert_eq!(
+            reconnects.check(PORT, &available, false, start + MIN_BACKOFF / 2),
+            Transition::Unchanged
+        );
+
+        let retry = start + MIN_BACKOFF;
+        assert_eq!(
+            reconnects.check(PORT, &available, false, retry),
+            Transition::Reconnect
+        );
+        reconnects.failed(retry);
+        assert_eq!(reconnects.backoff, MIN_BACKOFF * 4);
+        assert_eq!(
+            reconnects.check(PORT, &available, false, retry + MIN_BACKOFF),
+            Transition::Unchanged
+        );
+        assert_eq!(
+            reconnects.check(PORT, &available, false, retry + MIN_BACKOFF * 2),
+            Transition::Reconnect
+        );
+
+        reconnects.succeeded();
+        assert_eq!(reconnects.backoff, MIN_BACKOFF);
+    }
+}
@@ -1,32 +1,374 @@
-use crate::camera::{Camera, Direction};
-use crate::error::Result;
-use pelcodrs::{AutoCtrl, Direction as PelcoDirection, Message, MessageBuilder, Speed};
-use serialport::{DataBits, FlowControl, Parity, SerialPort, StopBits};
+use std::fmt;
+use std::io::ErrorKind;
+use std::time::Duration;
+
+use crate::camera::{Camera, Direction, RawReply, ResetKind, Speed, format_hex};
+use crate::error::{Error, Result};
+use crate::transport::{SerialTransport, Transport, read_until_quiet};
+use log::debug;
+use pelcodrs::{AutoCtrl, Message, MessageBuilder};
+
+const BAUD_RATE: u32 = 9000;
+const RAW_REPLY_TIMEOUT: Duration = Duration::from_millis(250);
+/// How long to wait for the reply to a command. Domes on a one-way RS-485 bus never answer, so
+/// this stays short and a missing reply is not an error.
+const RESPONSE_TIMEOUT: Duration = Duration::from_millis(50);
+/// How long to wait for the answer to a query, which unlike a command's reply is required.
+const QUERY_TIMEOUT: Duration = Duration::from_millis(250);
+
+const SYNC: u8 = 0xFF;
+
+// Command 2
+const PAN_RIGHT: u8 = 0x02;
+const PAN_LEFT: u8 = 0x04;
+const TILT_UP: u8 = 0x08;
+const TILT_DOWN: u8 = 0x10;
+const ZOOM_TELE: u8 = 0x20;
+const ZOOM_WIDE: u8 = 0x40;
+
+/// Pan speeds run from 0x00 to 0x3F, with 0xFF reserved for turbo. Before speeds could be
+/// chosen every move ran at 0x01, so the default of [`Speed::Medium`] pans and tilts faster.
+fn pan_speed(speed: Speed) -> u8 {
+    match speed {
+        Speed::Slow => 0x04,
+        Speed::Medium => 0x10,
+        Speed::Fast => 0x3F,
+        Speed::Turbo => 0xFF,
+    }
+}
+
+fn tilt_speed(speed: Speed) -> u8 {
+    match speed {
+        Speed::Slow => 0x04,
+        Speed::Medium => 0x10,
+        Speed::Fast | Speed::Turbo => 0x3F,
+    }
+}
+
+/// Zoom and focus speeds run from 0 (slowest) to 3.
+fn lens_speed(speed: Speed) -> u8 {
+    match speed {
+        Speed::Slow => 0x00,
+        Speed::Medium => 0x01,
+        Speed::Fast => 0x02,
+        Speed::Turbo => 0x03,
+    }
+}
+
+/// Calling this preset opens the on-screen menu on most Pelco-D domes.
+const MENU_PRESET: u8 = 95;
+/// Calling this preset starts the dome's preset tour.
+const TOUR_PRESET: u8 = 99;
+const IRIS_OPEN: u8 = 0x02;
+const IRIS_CLOSE: u8 = 0x04;
+const SET_AUXILIARY: u8 = 0x09;
+const CLEAR_AUXILIARY: u8 = 0x0B;
+const SET_ZOOM_SPEED: u8 = 0x25;
+const SET_FOCUS_SPEED: u8 = 0x27;
+const START_PATTERN: u8 = 0x1F;
+const STOP_PATTERN: u8 = 0x21;
+const RUN_PATTERN: u8 = 0x23;
+const REMOTE_RESET: u8 = 0x0F;
+const FACTORY_DEFAULTS: u8 = 0x29;
+const QUERY_PAN: u8 = 0x51;
+const QUERY_TILT: u8 = 0x53;
+const QUERY_ZOOM: u8 = 0x55;
+// The command 2 of the extended reply answering each query
+const PAN_POSITION: u8 = 0x59;
+const TILT_POSITION: u8 = 0x5B;
+const ZOOM_POSITION: u8 = 0x5D;
+
+/// The Pelco-D checksum: the sum of every byte between the sync byte and the checksum.
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+/// Builds a standard Pelco-D frame for commands `pelcodrs` does not provide.
+fn frame(address: u8, command1: u8, command2: u8, data1: u8, data2: u8) -> [u8; 7] {
+    let checksum = checksum(&[address, command1, command2, data1, data2]);
+
+    [SYNC, address, command1, command2, data1, data2, checksum]
+}
+
+/// A reply read back from a Pelco-D camera.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Response {
+    /// Sent after a command, carrying the camera's alarm inputs.
+    General { address: u8, alarms: u8 },
+    /// Sent in reply to a query, such as the pan position.
+    Extended {
+        address: u8,
+        command1: u8,
+        command2: u8,
+        data1: u8,
+        data2: u8,
+    },
+}
+
+impl Response {
+    const GENERAL_LENGTH: usize = 4;
+    const EXTENDED_LENGTH: usize = 7;
+
+    /// Parses one frame, starting at its sync byte.
+    fn parse(frame: &[u8]) -> Result<Self> {
+        let [SYNC, body @ .., received] = frame else {
+            return Err(Error::InvalidResponse(format_hex(frame)));
+        };
+
+        let expected = checksum(body);
+        if expected != *received {
+            return Err(Error::InvalidChecksum {
+                expected,
+                received: *received,
+            });
+        }
+
+        match *body {
+            [address, alarms] => Ok(Response::General { address, alarms }),
+            [address, command1, command2, data1, data2] => Ok(Response::Extended {
+                address,
+                command1,
+                command2,
+                data1,
+                data2,
+            }),
+            _ => Err(Error::InvalidResponse(format_hex(frame))),
+        }
+    }
+
+    /// Splits everything read back from the port into frames at each sync byte and describes them.
+    fn describe_all(bytes: &[u8]) -> Vec<String> {
+        let mut starts: Vec<usize> = bytes
+            .iter()
+            .enumerate()
+            .filter(|(_, byte)| **byte == SYNC)
+            .map(|(index, _)| index)
+            .collect();
+        if starts.first() != Some(&0) {
+            starts.insert(0, 0);
+        }
+
+        starts
+            .iter()
+            .zip(starts.iter().skip(1).chain([&bytes.len()]))
+            .filter(|(start, end)| start < end)
+            .map(|(start, end)| {
+                let frame = &bytes[*start..*end];
+                match Response::parse(frame) {
+                    Ok(response) => response.to_string(),
+                    Err(error) => format!("{error}: {}", format_hex(frame)),
+                }
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Response::General { address, alarms } => {
+                write!(f, "Acknowledged by {address} (alarms: {alarms:08b})")
+            }
+            Response::Extended {
+                address,
+                command1,
+                command2,
+                data1,
+                data2,
+            } => write!(
+                f,
+                "Reply from {address}: {command1:02X} {command2:02X} data {:04X}",
+                u16::from_be_bytes([*data1, *data2])
+            ),
+        }
+    }
+}
+
+/// What the dome is currently doing. Each motion frame replaces the one before, so stopping one
+/// axis means resending whatever the others are still doing.
+#[derive(Debug, Default, Clone, Copy)]
+struct Motion {
+    /// The pan and tilt bits of command 2.
+    pan_tilt: u8,
+    pan_speed: u8,
+    tilt_speed: u8,
+    /// The zoom bits of command 2.
+    zoom: u8,
+}
 
 #[derive(Debug)]
-pub struct PelcoCamera {
-    port: Box<dyn SerialPort>,
+pub struct PelcoCamera<T: Transport = SerialTransport> {
+    port: T,
+    address: u8,
+    motion: Motion,
+    /// The zoom speed last set on the camera, so it is only sent again when it changes.
+    zoom_speed: Option<u8>,
+    /// Whether to wait for a reply after each command. Cleared the first time one goes
+    /// unanswered, so a one-way bus does not pay [`RESPONSE_TIMEOUT`] on every frame, and set
+    /// again once a query is answered.
+    awaits_replies: bool,
 }
 
-impl PelcoCamera {
+impl<T: Transport> PelcoCamera<T> {
     fn send_message(&mut self, message: Message) -> Result<()> {
-        self.port.write_all(message.as_ref())?;
+        self.send(message.as_ref())?;
+        Ok(())
+    }
+
+    fn send_frame(&mut self, frame: [u8; 7]) -> Result<()> {
+        self.send(&frame)?;
+        Ok(())
+    }
+
+    /// Writes a command and returns the camera's reply, or `None` if it did not send one or
+    /// replies are no longer awaited.
+    fn send(&mut self, bytes: &[u8]) -> Result<Option<Response>> {
+        self.port.write_all(bytes)?;
+        if !self.awaits_replies {
+            return Ok(None);
+        }
+
+        let response = self.read_response(RESPONSE_TIMEOUT)?;
+        match response {
+            Some(Response::General { alarms, .. }) if alarms != 0 => {
+                debug!("Alarms active: {alarms:08b}")
+            }
+            Some(_) => {}
+            None => {
+                debug!(
+                    "No reply from {}, so no longer waiting for one",
+                    self.address
+                );
+                self.awaits_replies = false;
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Sends query `command2` and returns the data of the extended reply with command 2 `reply`.
+    fn query(&mut self, command2: u8, reply: u8) -> Result<u16> {
+        self.port
+            .write_all(&frame(self.address, 0x00, command2, 0x00, 0x00))?;
+
+        // Replies to earlier commands may still be waiting if they were not awaited
+        loop {
+            match self.read_response(QUERY_TIMEOUT)? {
+                Some(Response::Extended {
+                    command2,
+                    data1,
+                    data2,
+                    ..
+                }) if command2 == reply => {
+                    self.awaits_replies = true;
+                    return Ok(u16::from_be_bytes([data1, data2]));
+                }
+                Some(response) => debug!("Skipping {response}"),
+                None => return Err(Error::NoResponse),
+            }
+        }
+    }
+
+    /// Asks the camera where it is panned to, in hundredths of a degree.
+    pub fn pan_position(&mut self) -> Result<u16> {
+        self.query(QUERY_PAN, PAN_POSITION)
+    }
+
+    /// Asks the camera where it is tilted to, in hundredths of a degree.
+    pub fn tilt_position(&mut self) -> Result<u16> {
+        self.query(QUERY_TILT, TILT_POSITION)
+    }
+
+    /// Asks the camera how far it is zoomed in, as a fraction of 0xFFFF.
+    pub fn zoom_position(&mut self) -> Result<u16> {
+        self.query(QUERY_ZOOM, ZOOM_POSITION)
+    }
+
+    fn read_response(&mut self, timeout: Duration) -> Result<Option<Response>> {
+        let previous = self.port.timeout();
+        self.port.set_timeout(timeout)?;
+        let frame = self.read_frame();
+        self.port.set_timeout(previous)?;
+
+        match frame {
+            Ok(frame) => Response::parse(&frame).map(Some),
+            Err(error) if error.kind() == ErrorKind::TimedOut => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Reads one reply, which is a general reply unless its first four bytes fail the general
+    /// checksum, in which case it is the start of an extended one.
+    fn read_frame(&mut self) -> std::io::Result<Vec<u8>> {
+        // Skip anything before the sync byte, such as the tail of an earlier reply
+        let mut byte = [0u8; 1];
+        while byte[0] != SYNC {
+            self.port.read_exact(&mut byte)?;
+        }
+
+        let mut frame = vec![SYNC; Response::GENERAL_LENGTH];
+        self.port.read_exact(&mut frame[1..])?;
+        if checksum(&frame[1..Response::GENERAL_LENGTH - 1]) == frame[Response::GENERAL_LENGTH - 1]
+        {
+            return Ok(frame);
+        }
+
+        frame.resize(Response::EXTENDED_LENGTH, 0x00);
+        match self.port.read_exact(&mut frame[Response::GENERAL_LENGTH..]) {
+            Ok(()) => {}
+            // Nothing more came, so report the general reply's bad checksum
+            Err(error) if error.kind() == ErrorKind::TimedOut => {
+                frame.truncate(Response::GENERAL_LENGTH)
+            }
+            Err(error) => return Err(error),
+        }
+        Ok(frame)
+    }
+
+    /// Sends a frame that keeps every axis in `self.motion` moving and stops the rest.
+    fn send_motion(&mut self) -> Result<()> {
+        let Motion {
+            pan_tilt,
+            pan_speed,
+            tilt_speed,
+            zoom,
+        } = self.motion;
+        self.send_frame(frame(
+            self.address,
+            0x00,
+            pan_tilt | zoom,
+            pan_speed,
+            tilt_speed,
+        ))
+    }
+
+    fn set_zoom_speed(&mut self, speed: Speed) -> Result<()> {
+        let speed = lens_speed(speed);
+        if self.zoom_speed != Some(speed) {
+            self.send_frame(frame(self.address, 0x00, SET_ZOOM_SPEED, 0x00, speed))?;
+            self.zoom_speed = Some(speed);
+        }
         Ok(())
     }
+
+    /// Sends a momentary iris command, which menus use for enter (open) and back (close).
+    fn iris(&mut self, command1: u8) -> Result<()> {
+        self.send_frame(frame(self.address, command1, 0x00, 0x00, 0x00))?;
+        self.stop()
+    }
 }
 
-impl Camera for PelcoCamera {
-    fn new(path: &str) -> Result<Self>
+impl<T: Transport> Camera for PelcoCamera<T> {
+    fn new(path: &str, address: u8) -> Result<Self>
     where
         Self: Sized,
     {
         Ok(Self {
-            port: serialport::new(path, 9000)
-                .data_bits(DataBits::Eight)
-                .flow_control(FlowControl::None)
-                .parity(Parity::None)
-                .stop_bits(StopBits::One)
-                .open()?,
+            port: T::open(path, BAUD_RATE)?,
+            address,
+            motion: Motion::default(),
+            zoom_speed: None,
+            awaits_replies: true,
         })
     }
 
@@ -35,58 +377,478 @@ impl Camera for PelcoCamera {
     }
 
     fn power_on(&mut self) -> Result<()> {
-        self.send_message(MessageBuilder::new(1).camera_on().finalize()?)
+        self.send_message(MessageBuilder::new(self.address).camera_on().finalize()?)
     }
 
     fn power_off(&mut self) -> Result<()> {
-        self.send_message(MessageBuilder::new(1).camera_off().finalize()?)
+        self.send_message(MessageBuilder::new(self.address).camera_off().finalize()?)
     }
 
     fn autofocus(&mut self, state: bool) -> Result<()> {
         self.send_message(Message::auto_focus(
-            1,
+            self.address,
             if state { AutoCtrl::Auto } else { AutoCtrl::Off },
         )?)
     }
 
-    fn zoom_in(&mut self) -> Result<()> {
-        self.send_message(MessageBuilder::new(1).zoom_in().finalize()?)
+    fn zoom_in(&mut self, speed: Speed) -> Result<()> {
+        self.set_zoom_speed(speed)?;
+        self.motion.zoom = ZOOM_TELE;
+        self.send_motion()
+    }
+
+    fn zoom_out(&mut self, speed: Speed) -> Result<()> {
+        self.set_zoom_speed(speed)?;
+        self.motion.zoom = ZOOM_WIDE;
+        self.send_motion()
+    }
+
+    fn pan_tilt(&mut self, direction: Direction, speed: Speed) -> Result<()> {
+        (
+            self.motion.pan_tilt,
+            self.motion.pan_speed,
+            self.motion.tilt_speed,
+        ) = match direction {
+            Direction::Down => (TILT_DOWN, 0x00, tilt_speed(speed)),
+            Direction::Up => (TILT_UP, 0x00, tilt_speed(speed)),
+            Direction::Left => (PAN_LEFT, pan_speed(speed), 0x00),
+            Direction::Right => (PAN_RIGHT, pan_speed(speed), 0x00),
+        };
+        self.send_motion()
+    }
+
+    fn set_focus_speed(&mut self, speed: Speed) -> Result<()> {
+        self.send_frame(frame(
+            self.address,
+            0x00,
+            SET_FOCUS_SPEED,
+            0x00,
+            lens_speed(speed),
+        ))
+    }
+
+    fn stop_pan_tilt(&mut self) -> Result<()> {
+        self.motion.pan_tilt = 0x00;
+        self.motion.pan_speed = 0x00;
+        self.motion.tilt_speed = 0x00;
+        self.send_motion()
     }
 
-    fn zoom_out(&mut self) -> Result<()> {
-        self.send_message(MessageBuilder::new(1).zoom_out().finalize()?)
+    fn stop_zoom(&mut self) -> Result<()> {
+        self.motion.zoom = 0x00;
+        self.send_motion()
     }
 
-    fn pan_tilt(&mut self, direction: crate::camera::Direction) -> Result<()> {
-        self.send_message(
-            MessageBuilder::new(1)
-                .pan(Speed::Range(0.01))
-                .tilt(Speed::Range(0.01))
-                .direction(match direction {
-                    Direction::Down => PelcoDirection::DOWN,
-                    Direction::Up => PelcoDirection::UP,
-                    Direction::Left => PelcoDirection::LEFT,
-                    Direction::Right => PelcoDirection::RIGHT,
-                })
-                .finalize()?,
-        )
+    fn stop_focus(&mut self) -> Result<()> {
+        // Focus is never driven by hand here, so the current motion has no focus bits to clear
+        self.send_motion()
     }
 
     fn stop(&mut self) -> Result<()> {
-        self.send_message(MessageBuilder::new(1).stop().finalize()?)
+        self.motion = Motion::default();
+        self.send_message(MessageBuilder::new(self.address).stop().finalize()?)
     }
 
     fn set_preset(&mut self, preset: u8) -> Result<()> {
-        self.send_message(Message::set_preset(1, preset)?)
+        self.send_message(Message::set_preset(self.address, preset)?)
     }
 
     fn go_to_preset(&mut self, preset: u8) -> Result<()> {
-        self.send_message(Message::go_to_preset(1, preset)?)
+        // The dome abandons any manual move to head for the preset
+        self.motion = Motion::default();
+        self.send_message(Message::go_to_preset(self.address, preset)?)
+    }
+
+    fn set_preset_speed(&mut self, _preset: u8, _speed: u8) -> Result<()> {
+        // Pelco-D has no standard preset speed command; domes recall at their own configured speed
+        Err(Error::Unsupported(String::from("Preset speed")))
+    }
+
+    fn set_tally(&mut self, _state: bool) -> Result<()> {
+        Err(Error::Unsupported(String::from("Tally")))
+    }
+
+    fn open_menu(&mut self) -> Result<()> {
+        self.go_to_preset(MENU_PRESET)
+    }
+
+    fn close_menu(&mut self) -> Result<()> {
+        self.iris(IRIS_CLOSE)
+    }
+
+    fn menu_enter(&mut self) -> Result<()> {
+        self.iris(IRIS_OPEN)
+    }
+
+    fn menu_back(&mut self) -> Result<()> {
+        self.iris(IRIS_CLOSE)
+    }
+
+    fn menu_navigate(&mut self, direction: Direction) -> Result<()> {
+        self.pan_tilt(direction, Speed::Slow)?;
+        self.stop_pan_tilt()
+    }
+
+    fn auxiliary(&mut self, channel: u8, state: bool) -> Result<()> {
+        let command2 = if state {
+            SET_AUXILIARY
+        } else {
+            CLEAR_AUXILIARY
+        };
+        self.send_frame(frame(self.address, 0x00, command2, 0x00, channel))
+    }
+
+    fn start_pattern(&mut self, pattern: u8) -> Result<()> {
+        self.send_frame(frame(self.address, 0x00, START_PATTERN, 0x00, pattern))
+    }
+
+    fn stop_pattern(&mut self, pattern: u8) -> Result<()> {
+        self.send_frame(frame(self.address, 0x00, STOP_PATTERN, 0x00, pattern))
+    }
+
+    fn run_pattern(&mut self, pattern: u8) -> Result<()> {
+        self.send_frame(frame(self.address, 0x00, RUN_PATTERN, 0x00, pattern))
+    }
+
+    fn start_tour(&mut self) -> Result<()> {
+        self.go_to_preset(TOUR_PRESET)
+    }
+
+    fn reset(&mut self, kind: ResetKind) -> Result<()> {
+        let command2 = match kind {
+            ResetKind::Camera => REMOTE_RESET,
+            ResetKind::FactoryDefaults => FACTORY_DEFAULTS,
+            ResetKind::Lens => return Err(Error::Unsupported(String::from("Lens reset"))),
+        };
+        self.send_frame(frame(self.address, 0x00, command2, 0x00, 0x00))?;
+        self.zoom_speed = None;
+        Ok(())
+    }
+
+    fn send_raw(&mut self, bytes: &[u8]) -> Result<RawReply> {
+        // The bytes may change the zoom speed behind our back
+        self.zoom_speed = None;
+        self.port.write_all(bytes)?;
+        let received = read_until_quiet(&mut self.port, RAW_REPLY_TIMEOUT)?;
+
+        Ok(RawReply {
+            bytes: format_hex(&received),
+            decoded: Some(Response::describe_all(&received)),
+        })
     }
 }
 
-impl AsRef<PelcoCamera> for PelcoCamera {
-    fn as_ref(&self) -> &PelcoCamera {
+impl<T: Transport> AsRef<PelcoCamera<T>> for PelcoCamera<T> {
+    fn as_ref(&self) -> &PelcoCamera<T> {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    use test_case::test_case;
+
+    use crate::transport::TcpTransport;
+
+    fn connect() -> Result<(PelcoCamera<TcpTransport>, std::net::TcpStream)> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let camera = PelcoCamera::<TcpTransport>::new(&listener.local_addr()?.to_string(), 1)?;
+        let (server, _) = listener.accept()?;
+        Ok((camera, server))
+    }
+
+    #[test_case(b"\xFF\x01\x00\x01" => matches Ok(Response::General { address: 1, alarms: 0 }); "general")]
+    #[test_case(b"\xFF\x01\x00\x59\x12\x34\xA0" => matches Ok(Response::Extended { command2: 0x59, data1: 0x12, data2: 0x34, .. }); "extended")]
+    #[test_case(b"\xFF\x01\x00\x02" => matches Err(Error::InvalidChecksum { expected: 0x01, received: 0x02 }); "bad checksum")]
+    #[test_case(b"\xFF\x01\x01" => matches Err(Error::InvalidResponse(_)); "short")]
+    #[test_case(b"\x01\x00\x01\x02" => matches Err(Error::InvalidResponse(_)); "no sync")]
+    fn test_parse_response(frame: &[u8]) -> Result<Response> {
+        Response::parse(frame)
+    }
+
+    #[test]
+    fn test_describe_all() {
+        assert_eq!(
+            Response::describe_all(b"\x00\xFF\x01\x00\x01\xFF\x01\x00\x59\x12\x34\xA0"),
+            vec![
+                String::from("Invalid response: 00: 00"),
+                String::from("Acknowledged by 1 (alarms: 00000000)"),
+                String::from("Reply from 1: 00 59 data 1234"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bad_reply_checksum_is_reported() -> Result<()> {
+        let (mut camera, mut server) = connect()?;
+
+        // Zooming sets the zoom speed first, and each frame is acknowledged
+        server.write_all(b"\x00\xFF\x01\x00\x01\xFF\x01\x00\x01")?;
+        camera.zoom_in(Speed::Medium)?;
+
+        server.write_all(b"\xFF\x01\x00\x05")?;
+        assert!(matches!(
+            camera.zoom_out(Speed::Medium),
+            Err(Error::InvalidChecksum {
+                expected: 0x01,
+                received: 0x05
+            })
+        ));
+        Ok(())
+    }
+
+    #[test_case(QUERY_PAN, PAN_POSITION, PelcoCamera::pan_position; "pan")]
+    #[test_case(QUERY_TILT, TILT_POSITION, PelcoCamera::tilt_position; "tilt")]
+    #[test_case(QUERY_ZOOM, ZOOM_POSITION, PelcoCamera::zoom_position; "zoom")]
+    fn test_query_returns_position(
+        query: u8,
+        reply: u8,
+        position: fn(&mut PelcoCamera<TcpTransport>) -> Result<u16>,
+    ) -> Result<()> {
+        let (mut camera, mut server) = connect()?;
+
+        // A leftover acknowledgement is skipped on the way to the answer
+        server.write_all(b"\xFF\x01\x00\x01")?;
+        server.write_all(&frame(1, 0x00, reply, 0x12, 0x34))?;
+        assert_eq!(position(&mut camera)?, 0x1234);
+
+        let mut received = [0u8; 7];
+        server.read_exact(&mut received)?;
+        assert_eq!(received, frame(1, 0x00, query, 0x00, 0x00));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unanswered_query_is_an_error() -> Result<()> {
+        let (mut camera, _server) = connect()?;
+
+        assert!(matches!(camera.pan_position(), Err(Error::NoResponse)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_replies_are_awaited_until_one_is_missing() -> Result<()> {
+        let (mut camera, mut server) = connect()?;
+
+        server.write_all(b"\xFF\x01\x00\x01")?;
+        camera.stop()?;
+        assert!(camera.awaits_replies);
+
+        camera.stop()?;
+        assert!(!camera.awaits_replies);
+
+        // Answering a query shows the camera replies after all
+        server.write_all(b"\xFF\x01\x00\x59\x00\x10\x6A")?;
+        assert_eq!(camera.pan_position()?, 0x0010);
+        assert!(camera.awaits_replies);
+        Ok(())
+    }
+
+    #[test]
+    fn test_frame_checksum() {
+        assert_eq!(
+            frame(1, IRIS_OPEN, 0x00, 0x00, 0x00),
+            [0xFF, 0x01, 0x02, 0x00, 0x00, 0x00, 0x03]
+        );
+        assert_eq!(
+            frame(0xFF, 0x00, 0x07, 0x00, 0x5F),
+            [0xFF, 0xFF, 0x00, 0x07, 0x00, 0x5F, 0x65]
+        );
+    }
+
+    #[test]
+    fn test_auxiliary_over_tcp() -> Result<()> {
+        let (mut camera, mut server) = connect()?;
+
+        camera.auxiliary(2, true)?;
+        camera.auxiliary(2, false)?;
+
+        let mut received = [0u8; 14];
+        server.read_exact(&mut received)?;
+        assert_eq!(received[..7], [0xFF, 0x01, 0x00, 0x09, 0x00, 0x02, 0x0C]);
+        assert_eq!(received[7..], [0xFF, 0x01, 0x00, 0x0B, 0x00, 0x02, 0x0E]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_turbo_pan_over_tcp() -> Result<()> {
+        let (mut camera, mut server) = connect()?;
+
+        camera.pan_tilt(Direction::Right, Speed::Turbo)?;
+        camera.pan_tilt(Direction::Up, Speed::Turbo)?;
+
+        let mut received = [0u8; 14];
+        server.read_exact(&mut received)?;
+        assert_eq!(received[..7], [0xFF, 0x01, 0x00, 0x02, 0xFF, 0x00, 0x02]);
+        assert_eq!(received[7..], [0xFF, 0x01, 0x00, 0x08, 0x00, 0x3F, 0x48]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stop_zoom_keeps_panning() -> Result<()> {
+        let (mut camera, mut server) = connect()?;
+
+        camera.pan_tilt(Direction::Right, Speed::Fast)?;
+        camera.zoom_in(Speed::Fast)?;
+        camera.stop_zoom()?;
+        camera.stop_pan_tilt()?;
+
+        let mut received = [0u8; 35];
+        server.read_exact(&mut received)?;
+        assert_eq!(received[..7], frame(1, 0x00, PAN_RIGHT, 0x3F, 0x00));
+        assert_eq!(
+            received[14..21],
+            frame(1, 0x00, PAN_RIGHT | ZOOM_TELE, 0x3F, 0x00)
+        );
+        assert_eq!(received[21..28], frame(1, 0x00, PAN_RIGHT, 0x3F, 0x00));
+        assert_eq!(received[28..], frame(1, 0x00, 0x00, 0x00, 0x00));
+        Ok(())
+    }
+
+    #[test]
+    fn test_zoom_sets_speed_first() -> Result<()> {
+        let (mut camera, mut server) = connect()?;
+
+        camera.zoom_in(Speed::Fast)?;
+        camera.set_focus_speed(Speed::Slow)?;
+
+        let mut received = [0u8; 21];
+        server.read_exact(&mut received)?;
+        assert_eq!(received[..7], [0xFF, 0x01, 0x00, 0x25, 0x00, 0x02, 0x28]);
+        assert_eq!(received[14..], [0xFF, 0x01, 0x00, 0x27, 0x00, 0x00, 0x28]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_zoom_speed_is_sent_when_it_changes() -> Result<()> {
+        let (mut camera, mut server) = connect()?;
+
+        camera.zoom_in(Speed::Fast)?;
+        camera.zoom_out(Speed::Fast)?;
+        camera.zoom_in(Speed::Slow)?;
+
+        let mut received = [0u8; 35];
+        server.read_exact(&mut received)?;
+        assert_eq!(received[..7], frame(1, 0x00, SET_ZOOM_SPEED, 0x00, 0x02));
+        assert_eq!(received[7..14], frame(1, 0x00, ZOOM_TELE, 0x00, 0x00));
+        assert_eq!(received[14..21], frame(1, 0x00, ZOOM_WIDE, 0x00, 0x00));
+        assert_eq!(received[21..28], frame(1, 0x00, SET_ZOOM_SPEED, 0x00, 0x00));
+        assert_eq!(received[28..], frame(1, 0x00, ZOOM_TELE, 0x00, 0x00));
+        Ok(())
+    }
+
+    #[test]
+    fn test_patterns_over_tcp() -> Result<()> {
+        let (mut camera, mut server) = connect()?;
+
+        camera.start_pattern(1)?;
+        camera.stop_pattern(1)?;
+        camera.run_pattern(1)?;
+
+        let mut received = [0u8; 21];
+        server.read_exact(&mut received)?;
+        assert_eq!(received[..7], [0xFF, 0x01, 0x00, 0x1F, 0x00, 0x01, 0x21]);
+        assert_eq!(received[7..14], [0xFF, 0x01, 0x00, 0x21, 0x00, 0x01, 0x23]);
+        assert_eq!(received[14..], [0xFF, 0x01, 0x00, 0x23, 0x00, 0x01, 0x25]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reset_over_tcp() -> Result<()> {
+        let (mut camera, mut server) = connect()?;
+
+        camera.reset(ResetKind::Camera)?;
+        camera.reset(ResetKind::FactoryDefaults)?;
+        assert!(matches!(
+            camera.reset(ResetKind::Lens),
+            Err(Error::Unsupported(_))
+        ));
+
+        let mut received = [0u8; 14];
+        server.read_exact(&mut received)?;
+        assert_eq!(received[..7], [0xFF, 0x01, 0x00, 0x0F, 0x00, 0x00, 0x10]);
+        assert_eq!(received[7..], [0xFF, 0x01, 0x00, 0x29, 0x00, 0x00, 0x2A]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_commands_use_camera_address() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let mut camera = PelcoCamera::<TcpTransport>::new(&listener.local_addr()?.to_string(), 3)?;
+        let (mut server, _) = listener.accept()?;
+
+        camera.go_to_preset(5)?;
+        camera.set_preset(5)?;
+        camera.autofocus(true)?;
+        camera.open_menu()?;
+
+        let mut received = [0u8; 28];
+        server.read_exact(&mut received)?;
+        assert_eq!(received[..7], frame(3, 0x00, 0x07, 0x00, 5));
+        for frame in received.chunks(7) {
+            assert_eq!(frame[1], 3);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_menu_enter_over_tcp() -> Result<()> {
+        let (mut camera, mut server) = connect()?;
+
+        camera.menu_enter()?;
+
+        let mut received = [0u8; 7];
+        server.read_exact(&mut received)?;
+        assert_eq!(received, frame(1, IRIS_OPEN, 0x00, 0x00, 0x00));
+        Ok(())
+    }
+
+    #[test]
+    fn test_preset_speed_is_unsupported() -> Result<()> {
+        let (mut camera, mut server) = connect()?;
+
+        assert!(matches!(
+            camera.set_preset_speed(2, 5),
+            Err(Error::Unsupported(_))
+        ));
+        camera.go_to_preset(2)?;
+
+        // Nothing is sent for the speed, so the recall is the first frame
+        let mut received = [0u8; 7];
+        server.read_exact(&mut received)?;
+        assert_eq!(received, frame(1, 0x00, 0x07, 0x00, 2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_extended_reply_over_tcp() -> Result<()> {
+        let (mut camera, mut server) = connect()?;
+
+        server.write_all(b"\xFF\x01\x00\x59\x12\x34\xA0")?;
+        assert_eq!(
+            camera.send(&frame(1, 0x00, 0x51, 0x00, 0x00))?,
+            Some(Response::Extended {
+                address: 1,
+                command1: 0x00,
+                command2: 0x59,
+                data1: 0x12,
+                data2: 0x34,
+            })
+        );
+
+        server.write_all(b"\xFF\x01\x00\x01")?;
+        assert_eq!(
+            camera.send(&frame(1, 0x00, 0x00, 0x00, 0x00))?,
+            Some(Response::General {
+                address: 1,
+                alarms: 0
+            })
+        );
+        Ok(())
+    }
+}
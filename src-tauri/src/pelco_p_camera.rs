@@ -0,0 +1,420 @@
+use std::time::Duration;
+
+use crate::camera::{Camera, Direction, RawReply, ResetKind, Speed, format_hex};
+use crate::error::{Error, Result};
+use crate::transport::{SerialTransport, Transport, read_until_quiet};
+
+const BAUD_RATE: u32 = 4800;
+const RAW_REPLY_TIMEOUT: Duration = Duration::from_millis(250);
+
+const STX: u8 = 0xA0;
+const ETX: u8 = 0xAF;
+
+// Data 1
+const CAMERA_ON: u8 = 0x40;
+const CAMERA_ON_OFF: u8 = 0x10;
+const IRIS_CLOSE: u8 = 0x08;
+const IRIS_OPEN: u8 = 0x04;
+
+// Data 2
+const ZOOM_WIDE: u8 = 0x40;
+const ZOOM_TELE: u8 = 0x20;
+const TILT_DOWN: u8 = 0x10;
+const TILT_UP: u8 = 0x08;
+const PAN_LEFT: u8 = 0x04;
+const PAN_RIGHT: u8 = 0x02;
+
+// Extended commands share their numbers with Pelco-D
+const SET_PRESET: u8 = 0x03;
+const GO_TO_PRESET: u8 = 0x07;
+const AUTO_FOCUS: u8 = 0x2B;
+const SET_ZOOM_SPEED: u8 = 0x25;
+const SET_FOCUS_SPEED: u8 = 0x27;
+const SET_AUXILIARY: u8 = 0x09;
+const CLEAR_AUXILIARY: u8 = 0x0B;
+const START_PATTERN: u8 = 0x1F;
+const STOP_PATTERN: u8 = 0x21;
+const RUN_PATTERN: u8 = 0x23;
+const REMOTE_RESET: u8 = 0x0F;
+const FACTORY_DEFAULTS: u8 = 0x29;
+
+/// Calling this preset opens the on-screen menu on most Pelco domes.
+const MENU_PRESET: u8 = 95;
+/// Calling this preset starts the dome's preset tour.
+const TOUR_PRESET: u8 = 99;
+
+/// Pan speeds run from 0x00 to 0x3F, with 0x40 reserved for turbo.
+fn pan_speed(speed: Speed) -> u8 {
+    match speed {
+        Speed::Slow => 0x04,
+        Speed::Medium => 0x10,
+        Speed::Fast => 0x3F,
+        Speed::Turbo => 0x40,
+    }
+}
+
+fn tilt_speed(speed: Speed) -> u8 {
+    match speed {
+        Speed::Slow => 0x04,
+        Speed::Medium => 0x10,
+        Speed::Fast | Speed::Turbo => 0x3F,
+    }
+}
+
+/// Zoom and focus speeds run from 0 (slowest) to 3.
+fn lens_speed(speed: Speed) -> u8 {
+    match speed {
+        Speed::Slow => 0x00,
+        Speed::Medium => 0x01,
+        Speed::Fast => 0x02,
+        Speed::Turbo => 0x03,
+    }
+}
+
+/// Builds a Pelco-P frame, whose checksum is the XOR of every byte before it.
+fn frame(address: u8, data1: u8, data2: u8, data3: u8, data4: u8) -> [u8; 8] {
+    let bytes = [STX, address, data1, data2, data3, data4, ETX];
+    let checksum = bytes.iter().fold(0u8, |sum, byte| sum ^ byte);
+
+    [STX, address, data1, data2, data3, data4, ETX, checksum]
+}
+
+fn validate_preset(preset: u8) -> Result<u8> {
+    if preset == 0 {
+        Err(Error::Unsupported(String::from("Preset 0")))
+    } else {
+        Ok(preset)
+    }
+}
+
+/// What the dome is currently doing. As in Pelco-D, each motion frame replaces the one before.
+#[derive(Debug, Default, Clone, Copy)]
+struct Motion {
+    /// The pan and tilt bits of data 2.
+    pan_tilt: u8,
+    pan_speed: u8,
+    tilt_speed: u8,
+    /// The zoom bits of data 2.
+    zoom: u8,
+}
+
+#[derive(Debug)]
+pub struct PelcoPCamera<T: Transport = SerialTransport> {
+    port: T,
+    /// Pelco-P addresses start at 0, so address 0 is the same dome as Pelco-D address 1.
+    address: u8,
+    motion: Motion,
+    /// The zoom speed last set on the camera, so it is only sent again when it changes.
+    zoom_speed: Option<u8>,
+}
+
+impl<T: Transport> PelcoPCamera<T> {
+    fn send(&mut self, data1: u8, data2: u8, data3: u8, data4: u8) -> Result<()> {
+        self.port
+            .write_all(&frame(self.address, data1, data2, data3, data4))?;
+        Ok(())
+    }
+
+    /// Sends a frame that keeps every axis in `self.motion` moving and stops the rest.
+    fn send_motion(&mut self) -> Result<()> {
+        let Motion {
+            pan_tilt,
+            pan_speed,
+            tilt_speed,
+            zoom,
+        } = self.motion;
+        self.send(0x00, pan_tilt | zoom, pan_speed, tilt_speed)
+    }
+
+    fn set_zoom_speed(&mut self, speed: Speed) -> Result<()> {
+        let speed = lens_speed(speed);
+        if self.zoom_speed != Some(speed) {
+            self.send(0x00, SET_ZOOM_SPEED, 0x00, speed)?;
+            self.zoom_speed = Some(speed);
+        }
+        Ok(())
+    }
+
+    /// Sends a momentary iris command, which menus use for enter (open) and back (close).
+    fn iris(&mut self, data1: u8) -> Result<()> {
+        self.send(data1, 0x00, 0x00, 0x00)?;
+        self.stop()
+    }
+}
+
+impl<T: Transport> Camera for PelcoPCamera<T> {
+    fn new(path: &str, address: u8) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            port: T::open(path, BAUD_RATE)?,
+            address,
+            motion: Motion::default(),
+            zoom_speed: None,
+        })
+    }
+
+    fn name(&self) -> Option<String> {
+        self.port.name()
+    }
+
+    fn power_on(&mut self) -> Result<()> {
+        self.send(CAMERA_ON | CAMERA_ON_OFF, 0x00, 0x00, 0x00)
+    }
+
+    fn power_off(&mut self) -> Result<()> {
+        self.send(CAMERA_ON_OFF, 0x00, 0x00, 0x00)
+    }
+
+    fn autofocus(&mut self, state: bool) -> Result<()> {
+        self.send(0x00, AUTO_FOCUS, 0x00, if state { 0x00 } else { 0x01 })
+    }
+
+    fn zoom_in(&mut self, speed: Speed) -> Result<()> {
+        self.set_zoom_speed(speed)?;
+        self.motion.zoom = ZOOM_TELE;
+        self.send_motion()
+    }
+
+    fn zoom_out(&mut self, speed: Speed) -> Result<()> {
+        self.set_zoom_speed(speed)?;
+        self.motion.zoom = ZOOM_WIDE;
+        self.send_motion()
+    }
+
+    fn pan_tilt(&mut self, direction: Direction, speed: Speed) -> Result<()> {
+        (
+            self.motion.pan_tilt,
+            self.motion.pan_speed,
+            self.motion.tilt_speed,
+        ) = match direction {
+            Direction::Down => (TILT_DOWN, 0x00, tilt_speed(speed)),
+            Direction::Up => (TILT_UP, 0x00, tilt_speed(speed)),
+            Direction::Left => (PAN_LEFT, pan_speed(speed), 0x00),
+            Direction::Right => (PAN_RIGHT, pan_speed(speed), 0x00),
+        };
+        self.send_motion()
+    }
+
+    fn set_focus_speed(&mut self, speed: Speed) -> Result<()> {
+        self.send(0x00, SET_FOCUS_SPEED, 0x00, lens_speed(speed))
+    }
+
+    fn stop_pan_tilt(&mut self) -> Result<()> {
+        self.motion.pan_tilt = 0x00;
+        self.motion.pan_speed = 0x00;
+        self.motion.tilt_speed = 0x00;
+        self.send_motion()
+    }
+
+    fn stop_zoom(&mut self) -> Result<()> {
+        self.motion.zoom = 0x00;
+        self.send_motion()
+    }
+
+    fn stop_focus(&mut self) -> Result<()> {
+        // Focus is never driven by hand here, so the current motion has no focus bits to clear
+        self.send_motion()
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        self.motion = Motion::default();
+        self.send(0x00, 0x00, 0x00, 0x00)
+    }
+
+    fn set_preset(&mut self, preset: u8) -> Result<()> {
+        let preset = validate_preset(preset)?;
+        self.send(0x00, SET_PRESET, 0x00, preset)
+    }
+
+    fn go_to_preset(&mut self, preset: u8) -> Result<()> {
+        let preset = validate_preset(preset)?;
+        // The dome abandons any manual move to head for the preset
+        self.motion = Motion::default();
+        self.send(0x00, GO_TO_PRESET, 0x00, preset)
+    }
+
+    fn set_preset_speed(&mut self, _preset: u8, _speed: u8) -> Result<()> {
+        // Like Pelco-D, domes recall presets at their own configured speed
+        Err(Error::Unsupported(String::from("Preset speed")))
+    }
+
+    fn set_tally(&mut self, _state: bool) -> Result<()> {
+        Err(Error::Unsupported(String::from("Tally")))
+    }
+
+    fn open_menu(&mut self) -> Result<()> {
+        self.go_to_preset(MENU_PRESET)
+    }
+
+    fn close_menu(&mut self) -> Result<()> {
+        self.iris(IRIS_CLOSE)
+    }
+
+    fn menu_enter(&mut self) -> Result<()> {
+        self.iris(IRIS_OPEN)
+    }
+
+    fn menu_back(&mut self) -> Result<()> {
+        self.iris(IRIS_CLOSE)
+    }
+
+    fn menu_navigate(&mut self, direction: Direction) -> Result<()> {
+        self.pan_tilt(direction, Speed::Slow)?;
+        self.stop_pan_tilt()
+    }
+
+    fn auxiliary(&mut self, channel: u8, state: bool) -> Result<()> {
+        let data2 = if state {
+            SET_AUXILIARY
+        } else {
+            CLEAR_AUXILIARY
+        };
+        self.send(0x00, data2, 0x00, channel)
+    }
+
+    fn start_pattern(&mut self, pattern: u8) -> Result<()> {
+        self.send(0x00, START_PATTERN, 0x00, pattern)
+    }
+
+    fn stop_pattern(&mut self, pattern: u8) -> Result<()> {
+        self.send(0x00, STOP_PATTERN, 0x00, pattern)
+    }
+
+    fn run_pattern(&mut self, pattern: u8) -> Result<()> {
+        self.send(0x00, RUN_PATTERN, 0x00, pattern)
+    }
+
+    fn start_tour(&mut self) -> Result<()> {
+        self.go_to_preset(TOUR_PRESET)
+    }
+
+    fn reset(&mut self, kind: ResetKind) -> Result<()> {
+        let data2 = match kind {
+            ResetKind::Camera => REMOTE_RESET,
+            ResetKind::FactoryDefaults => FACTORY_DEFAULTS,
+            ResetKind::Lens => return Err(Error::Unsupported(String::from("Lens reset"))),
+        };
+        self.send(0x00, data2, 0x00, 0x00)?;
+        self.zoom_speed = None;
+        Ok(())
+    }
+
+    fn send_raw(&mut self, bytes: &[u8]) -> Result<RawReply> {
+        // The bytes may change the zoom speed behind our back
+        self.zoom_speed = None;
+        self.port.write_all(bytes)?;
+        let received = read_until_quiet(&mut self.port, RAW_REPLY_TIMEOUT)?;
+
+        Ok(RawReply {
+            bytes: format_hex(&received),
+            decoded: None,
+        })
+    }
+}
+
+impl<T: Transport> AsRef<PelcoPCamera<T>> for PelcoPCamera<T> {
+    fn as_ref(&self) -> &PelcoPCamera<T> {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    use test_case::test_case;
+
+    use crate::transport::TcpTransport;
+
+    #[test_case(frame(0, 0x00, ZOOM_TELE, 0x00, 0x00) => [0xA0, 0x00, 0x00, 0x20, 0x00, 0x00, 0xAF, 0x2F]; "zoom in")]
+    #[test_case(frame(0, 0x00, GO_TO_PRESET, 0x00, 95) => [0xA0, 0x00, 0x00, 0x07, 0x00, 0x5F, 0xAF, 0x57]; "go to preset")]
+    #[test_case(frame(0x1F, CAMERA_ON | CAMERA_ON_OFF, 0x00, 0x00, 0x00) => [0xA0, 0x1F, 0x50, 0x00, 0x00, 0x00, 0xAF, 0x40]; "power on")]
+    fn test_frame(frame: [u8; 8]) -> [u8; 8] {
+        frame
+    }
+
+    fn connect() -> Result<(PelcoPCamera<TcpTransport>, std::net::TcpStream)> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let camera = PelcoPCamera::<TcpTransport>::new(&listener.local_addr()?.to_string(), 0)?;
+        let (server, _) = listener.accept()?;
+        Ok((camera, server))
+    }
+
+    #[test]
+    fn test_pan_tilt_then_stop() -> Result<()> {
+        let (mut camera, mut server) = connect()?;
+
+        camera.pan_tilt(Direction::Left, Speed::Turbo)?;
+        camera.stop()?;
+
+        let mut received = [0u8; 16];
+        server.read_exact(&mut received)?;
+        assert_eq!(received[..8], frame(0, 0x00, PAN_LEFT, 0x40, 0x00));
+        assert_eq!(received[8..], frame(0, 0x00, 0x00, 0x00, 0x00));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stop_pan_tilt_keeps_zooming() -> Result<()> {
+        let (mut camera, mut server) = connect()?;
+
+        camera.zoom_out(Speed::Slow)?;
+        camera.pan_tilt(Direction::Down, Speed::Medium)?;
+        camera.stop_pan_tilt()?;
+        camera.stop_zoom()?;
+
+        let mut received = [0u8; 40];
+        server.read_exact(&mut received)?;
+        assert_eq!(received[8..16], frame(0, 0x00, ZOOM_WIDE, 0x00, 0x00));
+        assert_eq!(
+            received[16..24],
+            frame(0, 0x00, ZOOM_WIDE | TILT_DOWN, 0x00, 0x10)
+        );
+        assert_eq!(received[24..32], frame(0, 0x00, ZOOM_WIDE, 0x00, 0x00));
+        assert_eq!(received[32..], frame(0, 0x00, 0x00, 0x00, 0x00));
+        Ok(())
+    }
+
+    #[test]
+    fn test_zoom_speed_is_sent_when_it_changes() -> Result<()> {
+        let (mut camera, mut server) = connect()?;
+
+        camera.zoom_in(Speed::Medium)?;
+        camera.zoom_out(Speed::Medium)?;
+        camera.zoom_in(Speed::Turbo)?;
+
+        let mut received = [0u8; 40];
+        server.read_exact(&mut received)?;
+        assert_eq!(received[..8], frame(0, 0x00, SET_ZOOM_SPEED, 0x00, 0x01));
+        assert_eq!(received[8..16], frame(0, 0x00, ZOOM_TELE, 0x00, 0x00));
+        assert_eq!(received[16..24], frame(0, 0x00, ZOOM_WIDE, 0x00, 0x00));
+        assert_eq!(received[24..32], frame(0, 0x00, SET_ZOOM_SPEED, 0x00, 0x03));
+        assert_eq!(received[32..], frame(0, 0x00, ZOOM_TELE, 0x00, 0x00));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reset() -> Result<()> {
+        let (mut camera, mut server) = connect()?;
+
+        camera.reset(ResetKind::FactoryDefaults)?;
+
+        let mut received = [0u8; 8];
+        server.read_exact(&mut received)?;
+        assert_eq!(received, [0xA0, 0x00, 0x00, 0x29, 0x00, 0x00, 0xAF, 0x26]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_preset_zero_is_rejected() -> Result<()> {
+        let (mut camera, _server) = connect()?;
+
+        assert!(matches!(camera.go_to_preset(0), Err(Error::Unsupported(_))));
+        Ok(())
+    }
+}
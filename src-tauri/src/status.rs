@@ -0,0 +1,116 @@
+use std::{
+    collections::VecDeque,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use specta::Type;
+
+/// How many statuses [`StatusHistory`] keeps before dropping the oldest.
+pub const HISTORY_LENGTH: usize = 100;
+
+/// What a status is about.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Type)]
+pub enum StatusKind {
+    /// Opening, losing or reopening the camera's port.
+    #[default]
+    Connection,
+    /// The outcome of something the operator asked for.
+    Command,
+    /// Something the camera reported on its own, such as its IR remote being used.
+    Camera,
+    /// Reading or saving the settings.
+    Config,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Type)]
+pub enum Severity {
+    #[default]
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Type)]
+pub struct Status {
+    kind: StatusKind,
+    severity: Severity,
+    message: String,
+    /// Milliseconds since the Unix epoch, ready for JavaScript's `Date`.
+    timestamp: f64,
+    /// The Tauri command that led to this status, if any.
+    command: Option<String>,
+}
+
+impl Status {
+    pub fn new(kind: StatusKind, severity: Severity, message: &str, command: Option<&str>) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |elapsed| elapsed.as_secs_f64() * 1000.0);
+
+        Self {
+            kind,
+            severity,
+            message: String::from(message),
+            timestamp,
+            command: command.map(String::from),
+        }
+    }
+}
+
+/// The most recent statuses, oldest first, so errors can be reviewed after they scroll by.
+#[derive(Debug, Default)]
+pub struct StatusHistory(VecDeque<Status>);
+
+impl StatusHistory {
+    pub fn push(&mut self, status: Status) {
+        if self.0.len() == HISTORY_LENGTH {
+            self.0.pop_front();
+        }
+        self.0.push_back(status);
+    }
+
+    pub fn to_vec(&self) -> Vec<Status> {
+        self.0.iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_drops_oldest() {
+        let mut history = StatusHistory::default();
+
+        for index in 0..HISTORY_LENGTH + 2 {
+            history.push(Status::new(
+                StatusKind::Command,
+                Severity::Info,
+                &index.to_string(),
+                None,
+            ));
+        }
+
+        let statuses = history.to_vec();
+        assert_eq!(statuses.len(), HISTORY_LENGTH);
+        assert_eq!(statuses[0].message, "2");
+        assert_eq!(
+            statuses[HISTORY_LENGTH - 1].message,
+            (HISTORY_LENGTH + 1).to_string()
+        );
+    }
+
+    #[test]
+    fn test_status_records_command() {
+        let status = Status::new(
+            StatusKind::Command,
+            Severity::Error,
+            "Error: No port set",
+            Some("go_to_preset"),
+        );
+
+        assert_eq!(status.command.as_deref(), Some("go_to_preset"));
+        assert!(status.timestamp > 0.0);
+    }
+}
@@ -0,0 +1,378 @@
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use log::debug;
+use serialport::{DataBits, FlowControl, Parity, SerialPort, StopBits};
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A byte stream a camera driver talks over, such as a serial port or a TCP socket.
+///
+/// Reads give up with [`ErrorKind::TimedOut`] once the timeout passes without any data.
+pub trait Transport: Read + Write + Send {
+    /// Opens the transport at `path`. Transports that do not run over a serial line ignore
+    /// `baud_rate`.
+    fn open(path: &str, baud_rate: u32) -> io::Result<Self>
+    where
+        Self: Sized;
+
+    fn name(&self) -> Option<String>;
+    fn timeout(&self) -> Duration;
+    fn set_timeout(&mut self, timeout: Duration) -> io::Result<()>;
+
+    /// Returns how many bytes can be read without blocking.
+    fn bytes_to_read(&self) -> io::Result<u32>;
+
+    /// Closes and reopens the underlying connection.
+    fn reconnect(&mut self) -> io::Result<()>;
+
+    /// Returns a second handle for writing to the same connection, so a command can be sent
+    /// while another thread is blocked reading a reply.
+    fn try_clone_writer(&self) -> io::Result<Box<dyn Write + Send>>;
+}
+
+/// Reads until nothing arrives for `timeout`, for protocols whose replies have no terminator.
+pub fn read_until_quiet<T: Transport + ?Sized>(
+    transport: &mut T,
+    timeout: Duration,
+) -> io::Result<Vec<u8>> {
+    let previous = transport.timeout();
+    transport.set_timeout(timeout)?;
+
+    let mut received: Vec<u8> = Vec::new();
+    let mut buffer = [0u8; 64];
+    let result = loop {
+        match transport.read(&mut buffer) {
+            Ok(0) => break Ok(()),
+            Ok(count) => received.extend(&buffer[..count]),
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) if error.kind() == ErrorKind::TimedOut => break Ok(()),
+            Err(error) => break Err(error),
+        }
+    };
+
+    transport.set_timeout(previous)?;
+    result.map(|_| received)
+}
+
+#[derive(Debug)]
+pub struct SerialTransport {
+    port: Box<dyn SerialPort>,
+    baud_rate: u32,
+}
+
+impl SerialTransport {
+    pub fn new(port: Box<dyn SerialPort>) -> Self {
+        Self {
+            baud_rate: port.baud_rate().unwrap_or_default(),
+            port,
+        }
+    }
+
+    fn open_port(path: &str, baud_rate: u32, timeout: Duration) -> io::Result<Box<dyn SerialPort>> {
+        Ok(serialport::new(path, baud_rate)
+            .data_bits(DataBits::Eight)
+            .flow_control(FlowControl::None)
+            .parity(Parity::None)
+            .stop_bits(StopBits::One)
+            .timeout(timeout)
+            .open()?)
+    }
+}
+
+impl Transport for SerialTransport {
+    fn open(path: &str, baud_rate: u32) -> io::Result<Self> {
+        Ok(Self {
+            port: Self::open_port(path, baud_rate, DEFAULT_TIMEOUT)?,
+            baud_rate,
+        })
+    }
+
+    fn name(&self) -> Option<String> {
+        self.port.name()
+    }
+
+    fn timeout(&self) -> Duration {
+        self.port.timeout()
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        Ok(self.port.set_timeout(timeout)?)
+    }
+
+    fn bytes_to_read(&self) -> io::Result<u32> {
+        Ok(self.port.bytes_to_read()?)
+    }
+
+    fn reconnect(&mut self) -> io::Result<()> {
+        let path = self
+            .port
+            .name()
+            .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "serial port has no name"))?;
+
+        self.port = Self::open_port(&path, self.baud_rate, self.port.timeout())?;
+        Ok(())
+    }
+
+    fn try_clone_writer(&self) -> io::Result<Box<dyn Write + Send>> {
+        Ok(Box::new(self.port.try_clone()?))
+    }
+}
+
+impl Read for SerialTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.port.read(buf)
+    }
+}
+
+impl Write for SerialTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.port.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.port.flush()
+    }
+}
+
+/// A raw TCP socket, such as a serial device server passing bytes through to an RS-485 bus.
+#[derive(Debug)]
+pub struct TcpTransport {
+    address: String,
+    stream: TcpStream,
+    timeout: Duration,
+    /// Set when a read finds the connection closed, which a device server does when it
+    /// restarts or hands the port to another client, so the next write reconnects first.
+    closed: bool,
+}
+
+impl TcpTransport {
+    fn connect(address: &str, timeout: Duration) -> io::Result<TcpStream> {
+        let mut last_error = io::Error::new(ErrorKind::InvalidInput, "no address to connect to");
+
+        for socket_address in address.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&socket_address, timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(timeout))?;
+                    stream.set_nodelay(true)?;
+                    return Ok(stream);
+                }
+                Err(error) => last_error = error,
+            }
+        }
+
+        Err(last_error)
+    }
+
+    /// Whether the other end still has the connection open, checked without blocking. A write
+    /// into a connection the other end has closed succeeds locally, and the frame is lost.
+    fn is_alive(&self) -> io::Result<bool> {
+        self.stream.set_nonblocking(true)?;
+        let result = self.stream.peek(&mut [0u8; 1]);
+        self.stream.set_nonblocking(false)?;
+
+        match result {
+            Ok(count) => Ok(count > 0),
+            Err(error) if error.kind() == ErrorKind::WouldBlock => Ok(true),
+            Err(error) if is_disconnect(&error) => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+fn is_disconnect(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        ErrorKind::BrokenPipe
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::NotConnected
+    )
+}
+
+impl Transport for TcpTransport {
+    fn open(path: &str, _baud_rate: u32) -> io::Result<Self> {
+        Ok(Self {
+            address: String::from(path),
+            stream: Self::connect(path, DEFAULT_TIMEOUT)?,
+            timeout: DEFAULT_TIMEOUT,
+            closed: false,
+        })
+    }
+
+    fn name(&self) -> Option<String> {
+        Some(self.address.clone())
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        self.stream.set_read_timeout(Some(timeout))?;
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn bytes_to_read(&self) -> io::Result<u32> {
+        let mut buffer = [0u8; 256];
+
+        self.stream.set_nonblocking(true)?;
+        let result = self.stream.peek(&mut buffer);
+        self.stream.set_nonblocking(false)?;
+
+        match result {
+            Ok(count) => Ok(count as u32),
+            Err(error) if error.kind() == ErrorKind::WouldBlock => Ok(0),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn reconnect(&mut self) -> io::Result<()> {
+        self.stream = Self::connect(&self.address, self.timeout)?;
+        self.closed = false;
+        Ok(())
+    }
+
+    // A clone keeps writing to the old socket after a reconnect, so is only good until then
+    fn try_clone_writer(&self) -> io::Result<Box<dyn Write + Send>> {
+        Ok(Box::new(self.stream.try_clone()?))
+    }
+}
+
+impl Read for TcpTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Unix reports an expired read timeout as `WouldBlock`; match serial ports instead
+        let count = self.stream.read(buf).map_err(|error| match error.kind() {
+            ErrorKind::WouldBlock => io::Error::from(ErrorKind::TimedOut),
+            _ => error,
+        })?;
+        if count == 0 && !buf.is_empty() {
+            self.closed = true;
+        }
+        Ok(count)
+    }
+}
+
+impl Write for TcpTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.closed || !self.is_alive()? {
+            debug!("Reconnecting to {}", self.address);
+            self.reconnect()?;
+        }
+
+        match self.stream.write(buf) {
+            Err(error) if is_disconnect(&error) => {
+                debug!("Reconnecting to {} after {error}", self.address);
+                self.reconnect()?;
+                self.stream.write(buf)
+            }
+            result => result,
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_tcp_round_trip() -> io::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?.to_string();
+
+        let mut transport = TcpTransport::open(&address, 9600)?;
+        let (mut server, _) = listener.accept()?;
+
+        transport.write_all(b"\xFF\x01\x00\x08\x00\x20\x29")?;
+        let mut received = [0u8; 7];
+        server.read_exact(&mut received)?;
+        assert_eq!(&received, b"\xFF\x01\x00\x08\x00\x20\x29");
+
+        assert_eq!(transport.bytes_to_read()?, 0);
+        server.write_all(b"\x90\x41\xFF")?;
+        server.flush()?;
+        while transport.bytes_to_read()? < 3 {
+            std::thread::yield_now();
+        }
+
+        let mut reply = [0u8; 3];
+        transport.read_exact(&mut reply)?;
+        assert_eq!(&reply, b"\x90\x41\xFF");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tcp_read_times_out() -> io::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let mut transport = TcpTransport::open(&listener.local_addr()?.to_string(), 9600)?;
+        let _server = listener.accept()?;
+
+        transport.set_timeout(Duration::from_millis(10))?;
+        let error = transport.read(&mut [0u8; 1]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TimedOut);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tcp_reconnect() -> io::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let mut transport = TcpTransport::open(&listener.local_addr()?.to_string(), 9600)?;
+        drop(listener.accept()?);
+
+        transport.reconnect()?;
+        let (mut server, _) = listener.accept()?;
+        transport.write_all(b"\x01")?;
+
+        let mut received = [0u8; 1];
+        server.read_exact(&mut received)?;
+        assert_eq!(received, [0x01]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tcp_reconnects_after_reading_close() -> io::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let mut transport = TcpTransport::open(&listener.local_addr()?.to_string(), 9600)?;
+        drop(listener.accept()?);
+
+        assert_eq!(transport.read(&mut [0u8; 1])?, 0);
+
+        transport.write_all(b"\x01")?;
+        let (mut server, _) = listener.accept()?;
+
+        let mut received = [0u8; 1];
+        server.read_exact(&mut received)?;
+        assert_eq!(received, [0x01]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tcp_first_write_after_close_arrives() -> io::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let mut transport = TcpTransport::open(&listener.local_addr()?.to_string(), 9600)?;
+        drop(listener.accept()?);
+
+        transport.write_all(b"\x01")?;
+        let (mut server, _) = listener.accept()?;
+
+        let mut received = [0u8; 1];
+        server.read_exact(&mut received)?;
+        assert_eq!(received, [0x01]);
+
+        Ok(())
+    }
+}
@@ -1,4 +1,13 @@
-use std::sync::Mutex;
+use std::{
+    fs,
+    ops::RangeInclusive,
+    path::PathBuf,
+    sync::{
+        Mutex,
+        mpsc::{self, Receiver},
+    },
+    time::{Duration, Instant},
+};
 
 use log::debug;
 use serde::Serialize;
@@ -8,106 +17,1094 @@ use tauri_plugin_store::StoreExt;
 use tauri_specta::Event;
 
 use crate::{
-    camera::Camera,
+    camera::{
+        self, Camera, CameraEvent, Direction, MAX_AUXILIARY, MAX_PRESET_SPEED, PortInfo, Protocol,
+        Speed,
+    },
+    camera_worker::{Axis, CameraWorker, JobKind},
+    config::{
+        AuxiliaryConfig, CONFIG_FILE, CameraConfig, Config, PresetConfig, camera_name,
+        default_preset_name, preset_name,
+    },
     error::{Error, Result},
-    pelco_camera::PelcoCamera,
+    status::{Severity, Status, StatusHistory, StatusKind},
+    watchdog::Keepalive,
 };
 
+/// The watchdog timeouts, in milliseconds, the operator may choose from.
+const WATCHDOG_TIMEOUTS: RangeInclusive<u32> = 300..=10_000;
+
+/// The active camera's settings, taken from the UI state so the camera can be opened without
+/// holding its lock.
+pub struct Reopen {
+    protocol: Protocol,
+    port: String,
+    address: u8,
+    /// The UI state's generation when these were taken, which changes if the camera does.
+    generation: u64,
+}
+
+impl Reopen {
+    pub fn open(&self) -> Result<Box<dyn Camera>> {
+        self.protocol.open(&self.port, self.address)
+    }
+}
+
 #[derive(Default)]
 pub struct UIState {
-    camera: Option<Box<dyn Camera>>,
-    ports: Option<Vec<String>>,
-    status: String,
+    camera: Option<CameraWorker>,
+    /// Bumped whenever the camera is closed, so results from an earlier camera's worker are
+    /// dropped rather than applied to its replacement.
+    generation: u64,
+    config: Config,
+    ports: Option<Vec<PortInfo>>,
+    power: Option<bool>,
+    tally: bool,
+    menu: bool,
+    /// Whether the camera was last told to pan or tilt, and not yet to stop.
+    moving: bool,
+    /// Whether the camera was last told to zoom, and not yet to stop.
+    zooming: bool,
+    /// When the controls last confirmed they are still holding a move or zoom.
+    keepalive: Keepalive,
+    status: Status,
+    history: StatusHistory,
+    /// The Tauri command being handled, recorded on the statuses it leads to.
+    command: Option<String>,
 }
 
 impl UIState {
-    fn set_camera(&mut self, path: Option<&str>) -> Result<()> {
-        debug!("{path:?}");
+    /// Opens the active camera, closing whichever camera was open before.
+    fn set_camera<R: tauri::Runtime>(&mut self, app_handle: &tauri::AppHandle<R>) -> Result<()> {
+        let config = self.active_config().ok().cloned();
+        debug!("{config:?}");
 
-        // Drop the previous camera implicitly before setting a new one
-        self.camera = None;
+        self.close_camera();
 
-        if let Some(path) = path {
-            self.camera = Some(Box::new(PelcoCamera::new(path)?));
+        if let Some(CameraConfig {
+            protocol,
+            port: Some(port),
+            address,
+            ..
+        }) = config
+        {
+            let camera = protocol.open(&port, address)?;
+            self.install_camera(app_handle, camera);
         }
 
         Ok(())
     }
 
+    /// Starts a worker for a freshly opened `camera`, reporting its events to the UI state.
+    fn install_camera<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        mut camera: Box<dyn Camera>,
+    ) {
+        let app_handle = app_handle.clone();
+        camera.on_event(Box::new(move |event| {
+            with_ui_state(&app_handle, None, |ui| ui.handle_camera_event(event))
+        }));
+
+        self.camera = Some(CameraWorker::spawn(camera));
+    }
+
+    fn close_camera(&mut self) {
+        // Dropping the worker closes the previous camera and discards anything still queued
+        self.camera = None;
+        self.generation += 1;
+        self.power = None;
+        self.tally = false;
+        self.menu = false;
+        self.moving = false;
+        self.zooming = false;
+        self.keepalive.clear();
+    }
+
+    /// Reopens the active camera and reports whether it is connected.
+    fn connect<R: tauri::Runtime>(&mut self, app_handle: &tauri::AppHandle<R>) -> Result<()> {
+        self.set_camera(app_handle)?;
+
+        if self.camera.is_some() {
+            self.report(StatusKind::Connection, Severity::Info, "Connected")
+        } else {
+            self.report(StatusKind::Connection, Severity::Info, "Disconnected")
+        }
+    }
+
     pub fn initialize<R: tauri::Runtime>(&mut self, app: &tauri::AppHandle<R>) -> Result<()> {
-        let store = app.store("config.json")?;
-        if let Some(port_name) = store.get("port") {
-            if self.set_camera(port_name.as_str()).is_err() {
-                store.set("port", serde_json::Value::Null);
-                store.save()?;
+        let store = app.store(CONFIG_FILE)?;
+        let loaded = Config::load(store.entries());
+        store.close_resource();
+
+        let (migrated, warnings, unreadable) = match loaded {
+            Ok(loaded) => {
+                self.config = loaded.config;
+                (loaded.migrated, loaded.warnings, None)
             }
+            Err(error) => (false, Vec::new(), Some(error)),
+        };
+
+        self.refresh_ports()?;
+        self.follow_adapter(app)?;
+
+        if migrated {
+            self.save_config(app)?;
         }
-        store.close_resource();
 
-        if self.camera.is_some() {
-            self.set_status("Connected")?;
-        } else {
-            self.set_status("Disconnected")?;
+        // A camera that is unplugged at launch stays configured, just disconnected
+        if let Err(error) = self.connect(app) {
+            self.report(
+                StatusKind::Connection,
+                Severity::Warning,
+                &format!("Disconnected: {error}"),
+            )?;
+        }
+
+        for warning in warnings {
+            self.report(StatusKind::Config, Severity::Warning, &warning)?;
+        }
+
+        // Reported last so it stays on screen. Saving any setting replaces the whole file, so
+        // keep a copy of it to recover from
+        if let Some(error) = unreadable {
+            let message = match back_up_config(app) {
+                Ok(backup) => format!(
+                    "{error}. Using default settings; the old file was kept as {}",
+                    backup.display()
+                ),
+                Err(_) => format!("{error}. Using default settings"),
+            };
+            self.report(StatusKind::Config, Severity::Error, &message)?;
         }
 
         Ok(())
     }
 
-    pub fn camera(&mut self) -> Result<&mut dyn Camera> {
-        let camera = self.camera.as_mut().ok_or(Error::NoPortSet)?;
-        Ok(camera.as_mut())
+    fn worker(&self) -> Result<&CameraWorker> {
+        self.camera.as_ref().ok_or(Error::NoPortSet)
     }
 
-    pub fn set_camera_port<R: tauri::Runtime>(
+    /// Queues `job` on the camera's worker and returns straight away. Once the job has run,
+    /// `done` is applied to the UI state and the result emitted, unless the camera was closed
+    /// in the meantime.
+    pub fn run<R, J, D>(&self, app_handle: &tauri::AppHandle<R>, job: J, done: D) -> Result<()>
+    where
+        R: tauri::Runtime,
+        J: FnOnce(&mut dyn Camera) -> Result<()> + Send + 'static,
+        D: FnOnce(&mut UIState) -> Result<()> + Send + 'static,
+    {
+        self.queue(app_handle, JobKind::Command, job, done)
+    }
+
+    /// Like [`UIState::run`], but queues `job` as `kind`, so stops can clear what they stop
+    /// from the queue and cancel it in flight.
+    pub fn queue<R, J, D>(
+        &self,
+        app_handle: &tauri::AppHandle<R>,
+        kind: JobKind,
+        job: J,
+        done: D,
+    ) -> Result<()>
+    where
+        R: tauri::Runtime,
+        J: FnOnce(&mut dyn Camera) -> Result<()> + Send + 'static,
+        D: FnOnce(&mut UIState) -> Result<()> + Send + 'static,
+    {
+        let app_handle = app_handle.clone();
+        let command = self.command.clone();
+        let generation = self.generation;
+        let report = move |result: Result<()>| {
+            with_ui_state(&app_handle, command.as_deref(), |ui| {
+                if ui.generation != generation {
+                    return Ok(());
+                }
+                match result {
+                    // The stop that canceled it reports instead
+                    Err(error) if error.is_canceled() => Ok(()),
+                    result => {
+                        result?;
+                        done(ui)
+                    }
+                }
+            });
+        };
+
+        self.worker()?.enqueue(kind, job, report)
+    }
+
+    /// Queues `job` on the camera's worker and returns a receiver for its result, so the caller
+    /// can wait for it after releasing the UI state.
+    pub fn call<T, F>(&self, job: F) -> Result<Receiver<Result<T>>>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut dyn Camera) -> Result<T> + Send + 'static,
+    {
+        self.worker()?.call(job)
+    }
+
+    fn active_config(&self) -> Result<&CameraConfig> {
+        self.config
+            .active_camera
+            .and_then(|id| self.config.cameras.iter().find(|config| config.id == id))
+            .ok_or(Error::NoCameraSelected)
+    }
+
+    fn active_config_mut(&mut self) -> Result<&mut CameraConfig> {
+        self.config
+            .active_camera
+            .and_then(|id| {
+                self.config
+                    .cameras
+                    .iter_mut()
+                    .find(|config| config.id == id)
+            })
+            .ok_or(Error::NoCameraSelected)
+    }
+
+    fn config_mut(&mut self, id: u32) -> Result<&mut CameraConfig> {
+        self.config
+            .cameras
+            .iter_mut()
+            .find(|config| config.id == id)
+            .ok_or(Error::InvalidCamera(id))
+    }
+
+    /// Adds a camera and makes it the active one.
+    pub fn add_camera<R: tauri::Runtime>(
         &mut self,
         app_handle: &tauri::AppHandle<R>,
-        path: Option<&str>,
+        name: &str,
+        protocol: Protocol,
+        port: Option<&str>,
+        address: u8,
+    ) -> Result<()> {
+        let address = protocol.validate_address(address)?;
+        let id = self
+            .config
+            .cameras
+            .iter()
+            .map(|config| config.id)
+            .max()
+            .unwrap_or_default()
+            + 1;
+
+        self.config.cameras.push(CameraConfig {
+            id,
+            name: camera_name(id, name),
+            protocol,
+            port: port.map(String::from),
+            serial_number: self.serial_number(port),
+            address,
+        });
+        self.config.active_camera = Some(id);
+        self.save_config(app_handle)?;
+
+        self.connect(app_handle)
+    }
+
+    pub fn remove_camera<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        id: u32,
     ) -> Result<()> {
-        self.set_camera(path)?;
+        self.config_mut(id)?;
+        self.config.cameras.retain(|config| config.id != id);
 
-        let store = app_handle.store("config.json")?;
-        store.set("port", path);
+        if self.config.active_camera == Some(id) {
+            self.config.active_camera = None;
+            self.save_config(app_handle)?;
+            self.connect(app_handle)
+        } else {
+            self.save_config(app_handle)
+        }
+    }
+
+    pub fn rename_camera<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        id: u32,
+        name: &str,
+    ) -> Result<()> {
+        self.config_mut(id)?.name = camera_name(id, name);
+        self.save_config(app_handle)
+    }
+
+    /// Switches every command over to the camera `id`.
+    pub fn select_camera<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        id: u32,
+    ) -> Result<()> {
+        self.config_mut(id)?;
+        self.config.active_camera = Some(id);
+        self.save_config(app_handle)?;
+
+        self.connect(app_handle)
+    }
+
+    fn save_config<R: tauri::Runtime>(&self, app_handle: &tauri::AppHandle<R>) -> Result<()> {
+        let store = app_handle.store(CONFIG_FILE)?;
+        let entries = self.config.entries();
+        // Drops keys earlier layouts used, such as the single `port`
+        for key in store.keys() {
+            if !entries.contains_key(&key) {
+                store.delete(key);
+            }
+        }
+        for (key, value) in entries {
+            store.set(key, value);
+        }
         store.save()?;
         store.close_resource();
 
-        if self.camera.is_some() {
-            self.set_status("Connected")?;
+        Ok(())
+    }
+
+    pub fn set_camera_port<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        path: Option<&str>,
+    ) -> Result<()> {
+        let serial_number = self.serial_number(path);
+        let config = self.active_config_mut()?;
+        config.port = path.map(String::from);
+        config.serial_number = serial_number;
+        self.save_config(app_handle)?;
+
+        self.connect(app_handle)
+    }
+
+    pub fn set_protocol<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        protocol: Protocol,
+    ) -> Result<()> {
+        let config = self.active_config_mut()?;
+        config.protocol = protocol;
+        if protocol.validate_address(config.address).is_err() {
+            config.address = *protocol.addresses().start();
+        }
+        self.save_config(app_handle)?;
+
+        // Reopen the current port so the new protocol takes effect immediately
+        self.connect(app_handle)
+    }
+
+    pub fn set_camera_address<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        address: u8,
+    ) -> Result<()> {
+        let config = self.active_config_mut()?;
+        config.address = config.protocol.validate_address(address)?;
+        self.save_config(app_handle)?;
+
+        self.connect(app_handle)
+    }
+
+    pub fn set_preset_speed<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        preset: u8,
+        speed: Option<u8>,
+    ) -> Result<()> {
+        if let Some(speed) = speed.filter(|speed| !(1..=MAX_PRESET_SPEED).contains(speed)) {
+            return Err(Error::InvalidSpeed(speed));
+        }
+
+        self.preset_mut(preset)?.speed = speed;
+        self.save_config(app_handle)
+    }
+
+    pub fn presets(&self) -> &[PresetConfig] {
+        &self.config.presets
+    }
+
+    fn preset_mut(&mut self, preset: u8) -> Result<&mut PresetConfig> {
+        self.config
+            .presets
+            .iter_mut()
+            .find(|config| config.number == preset)
+            .ok_or(Error::InvalidPreset(preset))
+    }
+
+    /// The name shown for `preset`, even if it is not in the library.
+    fn preset_name(&self, preset: u8) -> String {
+        self.config
+            .presets
+            .iter()
+            .find(|config| config.number == preset)
+            .map_or_else(|| default_preset_name(preset), |config| config.name.clone())
+    }
+
+    /// Adds `preset` to the end of the library.
+    pub fn create_preset<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        preset: u8,
+        name: &str,
+        color: Option<&str>,
+    ) -> Result<()> {
+        if self
+            .config
+            .presets
+            .iter()
+            .any(|config| config.number == preset)
+        {
+            return Err(Error::DuplicatePreset(preset));
+        }
+
+        self.config.presets.push(PresetConfig {
+            number: preset,
+            name: preset_name(preset, name),
+            color: color.map(String::from),
+            speed: None,
+        });
+        self.save_config(app_handle)
+    }
+
+    pub fn rename_preset<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        preset: u8,
+        name: &str,
+    ) -> Result<()> {
+        self.preset_mut(preset)?.name = preset_name(preset, name);
+        self.save_config(app_handle)
+    }
+
+    pub fn set_preset_color<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        preset: u8,
+        color: Option<&str>,
+    ) -> Result<()> {
+        self.preset_mut(preset)?.color = color.map(String::from);
+        self.save_config(app_handle)
+    }
+
+    /// Rearranges the library to match `order`, which must list every preset exactly once.
+    pub fn reorder_presets<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        order: &[u8],
+    ) -> Result<()> {
+        if let Some(preset) = order.iter().find(|preset| {
+            !self
+                .config
+                .presets
+                .iter()
+                .any(|config| config.number == **preset)
+        }) {
+            return Err(Error::InvalidPreset(*preset));
+        }
+        if let Some(config) = self
+            .config
+            .presets
+            .iter()
+            .find(|config| !order.contains(&config.number))
+        {
+            return Err(Error::InvalidPreset(config.number));
+        }
+
+        self.config
+            .presets
+            .sort_by_key(|config| order.iter().position(|preset| *preset == config.number));
+        self.save_config(app_handle)
+    }
+
+    pub fn delete_preset<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        preset: u8,
+    ) -> Result<()> {
+        self.preset_mut(preset)?;
+        self.config.presets.retain(|config| config.number != preset);
+        self.save_config(app_handle)
+    }
+
+    /// Remembers a `host:port` endpoint so it is offered alongside the serial ports.
+    pub fn add_endpoint<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        endpoint: &str,
+    ) -> Result<()> {
+        let endpoint = endpoint.trim();
+        if !camera::is_network_address(endpoint) {
+            return Err(Error::InvalidEndpoint(String::from(endpoint)));
+        }
+
+        if !self
+            .config
+            .endpoints
+            .iter()
+            .any(|existing| existing == endpoint)
+        {
+            self.config.endpoints.push(String::from(endpoint));
+            self.save_config(app_handle)?;
+        }
+
+        self.refresh_ports()
+    }
+
+    pub fn remove_endpoint<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        endpoint: &str,
+    ) -> Result<()> {
+        self.config
+            .endpoints
+            .retain(|existing| existing != endpoint);
+        self.save_config(app_handle)?;
+
+        // Forgetting an endpoint unassigns it, disconnecting the active camera if it used it
+        let active_uses_endpoint = self
+            .active_config()
+            .is_ok_and(|config| config.port.as_deref() == Some(endpoint));
+        for config in &mut self.config.cameras {
+            if config.port.as_deref() == Some(endpoint) {
+                config.port = None;
+            }
+        }
+        self.save_config(app_handle)?;
+        if active_uses_endpoint {
+            self.connect(app_handle)?;
+        }
+
+        self.refresh_ports()
+    }
+
+    /// Recalls `preset`, first applying its stored recall speed if it has one.
+    pub fn go_to_preset<R: tauri::Runtime>(
+        &self,
+        app_handle: &tauri::AppHandle<R>,
+        preset: u8,
+    ) -> Result<()> {
+        let speed = self
+            .config
+            .presets
+            .iter()
+            .find(|config| config.number == preset)
+            .and_then(|config| config.speed);
+        let name = self.preset_name(preset);
+
+        self.queue(
+            app_handle,
+            JobKind::Recall,
+            move |camera| {
+                if let Some(speed) = speed {
+                    match camera.set_preset_speed(preset, speed) {
+                        // The camera recalls at its own speed, but should still recall
+                        Ok(()) | Err(Error::Unsupported(_)) => {}
+                        Err(error) => return Err(error),
+                    }
+                }
+                camera.go_to_preset(preset)
+            },
+            move |ui| ui.set_status(&name),
+        )
+    }
+
+    /// Stores the camera's current position as `preset`.
+    pub fn set_preset<R: tauri::Runtime>(
+        &self,
+        app_handle: &tauri::AppHandle<R>,
+        preset: u8,
+    ) -> Result<()> {
+        let status = format!("Set {}", self.preset_name(preset));
+
+        self.run(
+            app_handle,
+            move |camera| camera.set_preset(preset),
+            move |ui| ui.set_status(&status),
+        )
+    }
+
+    pub fn camera_power<R: tauri::Runtime>(
+        &self,
+        app_handle: &tauri::AppHandle<R>,
+        power: bool,
+    ) -> Result<()> {
+        self.run(
+            app_handle,
+            move |camera| {
+                if power {
+                    camera.power_on()
+                } else {
+                    camera.power_off()
+                }
+            },
+            move |ui| {
+                ui.power = Some(power);
+                ui.set_status(if power { "Power on" } else { "Power off" })
+            },
+        )
+    }
+
+    pub fn set_tally<R: tauri::Runtime>(
+        &self,
+        app_handle: &tauri::AppHandle<R>,
+        tally: bool,
+    ) -> Result<()> {
+        self.run(
+            app_handle,
+            move |camera| camera.set_tally(tally),
+            move |ui| {
+                ui.tally = tally;
+                ui.set_status(if tally { "Tally on" } else { "Tally off" })
+            },
+        )
+    }
+
+    /// Labels auxiliary output `channel`, or forgets it when `label` is empty.
+    pub fn set_auxiliary_label<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        channel: u8,
+        label: &str,
+    ) -> Result<()> {
+        validate_auxiliary(channel)?;
+
+        let label = label.trim();
+        self.config
+            .auxiliaries
+            .retain(|config| config.channel != channel);
+        if !label.is_empty() {
+            self.config.auxiliaries.push(AuxiliaryConfig {
+                channel,
+                label: String::from(label),
+            });
+            self.config.auxiliaries.sort_by_key(|config| config.channel);
+        }
+
+        self.save_config(app_handle)
+    }
+
+    pub fn auxiliary<R: tauri::Runtime>(
+        &self,
+        app_handle: &tauri::AppHandle<R>,
+        channel: u8,
+        state: bool,
+    ) -> Result<()> {
+        validate_auxiliary(channel)?;
+
+        let label = self
+            .config
+            .auxiliaries
+            .iter()
+            .find(|config| config.channel == channel)
+            .map_or_else(
+                || format!("Auxiliary {channel}"),
+                |config| config.label.clone(),
+            );
+        let status = format!("{label} {}", if state { "on" } else { "off" });
+
+        self.run(
+            app_handle,
+            move |camera| camera.auxiliary(channel, state),
+            move |ui| ui.set_status(&status),
+        )
+    }
+
+    pub fn open_menu<R: tauri::Runtime>(&self, app_handle: &tauri::AppHandle<R>) -> Result<()> {
+        self.run(
+            app_handle,
+            |camera| camera.open_menu(),
+            |ui| {
+                ui.menu = true;
+                ui.set_status("Camera menu open")
+            },
+        )
+    }
+
+    pub fn close_menu<R: tauri::Runtime>(&self, app_handle: &tauri::AppHandle<R>) -> Result<()> {
+        self.run(
+            app_handle,
+            |camera| camera.close_menu(),
+            |ui| {
+                ui.menu = false;
+                ui.set_status("Camera menu closed")
+            },
+        )
+    }
+
+    /// Pans or tilts the camera, or moves the menu highlight while the menu is open.
+    pub fn move_camera<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        direction: Direction,
+        speed: Speed,
+    ) -> Result<()> {
+        let menu = self.menu;
+        let status = format!(
+            "Moving {}",
+            match direction {
+                Direction::Down => "down",
+                Direction::Up => "up",
+                Direction::Left => "left",
+                Direction::Right => "right",
+            }
+        );
+
+        // Menu navigation stops on its own, so a stop must not discard it
+        let kind = if menu {
+            JobKind::Command
         } else {
-            self.set_status("Disconnected")?;
+            JobKind::Drive(Axis::PanTilt)
+        };
+        self.queue(
+            app_handle,
+            kind,
+            move |camera| {
+                if menu {
+                    camera.menu_navigate(direction)
+                } else {
+                    camera.pan_tilt(direction, speed)
+                }
+            },
+            move |ui| ui.set_status(&status),
+        )?;
+
+        if !menu {
+            self.moving = true;
+            self.keepalive.refresh(Instant::now());
         }
+        Ok(())
+    }
+
+    pub fn stop_move<R: tauri::Runtime>(&mut self, app_handle: &tauri::AppHandle<R>) -> Result<()> {
+        // Menu navigation stops on its own
+        if self.menu {
+            Ok(())
+        } else {
+            self.moving = false;
+            self.queue(
+                app_handle,
+                JobKind::Stop(Axis::PanTilt),
+                |camera| camera.stop_pan_tilt(),
+                |ui| ui.set_status("Done moving"),
+            )
+        }
+    }
+
+    pub fn zoom<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        zoom_in: bool,
+        speed: Speed,
+    ) -> Result<()> {
+        self.queue(
+            app_handle,
+            JobKind::Drive(Axis::Zoom),
+            move |camera| {
+                if zoom_in {
+                    camera.zoom_in(speed)
+                } else {
+                    camera.zoom_out(speed)
+                }
+            },
+            move |ui| ui.set_status(if zoom_in { "Zooming in" } else { "Zooming out" }),
+        )?;
 
+        self.zooming = true;
+        self.keepalive.refresh(Instant::now());
         Ok(())
     }
 
+    pub fn stop_zoom<R: tauri::Runtime>(&mut self, app_handle: &tauri::AppHandle<R>) -> Result<()> {
+        self.zooming = false;
+        self.queue(
+            app_handle,
+            JobKind::Stop(Axis::Zoom),
+            |camera| camera.stop_zoom(),
+            |ui| ui.set_status("Done zooming"),
+        )
+    }
+
+    /// Halts the camera ahead of anything else it was asked to do.
+    pub fn emergency_stop<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+    ) -> Result<()> {
+        self.moving = false;
+        self.zooming = false;
+        self.queue(
+            app_handle,
+            JobKind::Halt,
+            |camera| camera.stop(),
+            |ui| ui.report(StatusKind::Command, Severity::Warning, "Emergency stop"),
+        )
+    }
+
+    /// Records that the controls are still holding the current move or zoom.
+    pub fn keep_alive(&mut self) {
+        if self.moving || self.zooming {
+            self.keepalive.refresh(Instant::now());
+        }
+    }
+
+    pub fn set_watchdog_timeout<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        timeout: u32,
+    ) -> Result<()> {
+        if !WATCHDOG_TIMEOUTS.contains(&timeout) {
+            return Err(Error::InvalidWatchdogTimeout(timeout));
+        }
+        self.config.watchdog_timeout = timeout;
+
+        self.save_config(app_handle)
+    }
+
+    /// Stops a move or zoom that has gone without a keepalive for longer than the timeout, as
+    /// happens when the webview hangs before it can send the stop.
+    pub fn check_watchdog<R: tauri::Runtime>(&mut self, app_handle: &tauri::AppHandle<R>) {
+        let timeout = Duration::from_millis(u64::from(self.config.watchdog_timeout));
+
+        if self.keepalive.expired(Instant::now(), timeout) {
+            self.halt(app_handle, "Stopped: the controls stopped responding");
+        }
+    }
+
+    /// Stops any move or zoom in progress, reporting `reason` once the camera has been told.
+    /// The returned receiver hears when that happens, for callers that can't wait for the
+    /// event, and is `None` when there was nothing to stop.
+    pub fn halt<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        reason: &str,
+    ) -> Option<Receiver<()>> {
+        self.keepalive.clear();
+        if !self.moving && !self.zooming {
+            return None;
+        }
+        self.moving = false;
+        self.zooming = false;
+
+        let (stopped, receiver) = mpsc::channel();
+        let reason = String::from(reason);
+        let result = self.queue(
+            app_handle,
+            JobKind::Halt,
+            |camera| camera.stop(),
+            move |ui| {
+                let _ = stopped.send(());
+                ui.report(StatusKind::Command, Severity::Warning, &reason)
+            },
+        );
+
+        match result {
+            Ok(()) => Some(receiver),
+            Err(error) => {
+                debug!("Halting failed: {error}");
+                None
+            }
+        }
+    }
+
+    pub fn handle_camera_event(&mut self, event: CameraEvent) -> Result<()> {
+        debug!("Camera event: {event:?}");
+
+        match event {
+            CameraEvent::Power(power) => {
+                self.power = Some(power);
+                self.report(
+                    StatusKind::Camera,
+                    Severity::Info,
+                    if power {
+                        "Powered on from remote"
+                    } else {
+                        "Powered off from remote"
+                    },
+                )
+            }
+            CameraEvent::NetworkChange => {
+                self.report(StatusKind::Camera, Severity::Info, "Camera address changed")
+            }
+        }
+    }
+
+    /// Reports the successful outcome of a command.
     pub fn set_status(&mut self, status: &str) -> Result<()> {
-        self.status = String::from(status);
+        self.report(StatusKind::Command, Severity::Info, status)
+    }
+
+    pub fn report(&mut self, kind: StatusKind, severity: Severity, message: &str) -> Result<()> {
+        self.status = Status::new(kind, severity, message, self.command.as_deref());
+        self.history.push(self.status.clone());
         Ok(())
     }
 
+    pub fn status_history(&self) -> Vec<Status> {
+        self.history.to_vec()
+    }
+
     pub fn refresh_ports(&mut self) -> Result<()> {
-        self.ports = Some(
-            serialport::available_ports()?
-                .into_iter()
-                .map(|port| port.port_name)
-                .collect(),
+        self.set_available_ports(camera::available_ports()?);
+        Ok(())
+    }
+
+    /// Offers `ports` alongside the saved endpoints, returning whether the list changed.
+    pub fn set_available_ports(&mut self, mut ports: Vec<PortInfo>) -> bool {
+        ports.extend(
+            self.config
+                .endpoints
+                .iter()
+                .map(|endpoint| PortInfo::named(endpoint)),
         );
+
+        let changed = self.ports.as_ref() != Some(&ports);
+        self.ports = Some(ports);
+        changed
+    }
+
+    fn serial_number(&self, path: Option<&str>) -> Option<String> {
+        self.ports
+            .iter()
+            .flatten()
+            .find(|port| Some(port.name.as_str()) == path)
+            .and_then(|port| port.serial_number.clone())
+    }
+
+    /// Points the active camera at its adapter's current port when the OS has renamed it, such
+    /// as after a reboot or plugging it into another socket. Returns whether the port changed.
+    pub fn follow_adapter<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+    ) -> Result<bool> {
+        let Some(serial_number) = self
+            .active_config()
+            .ok()
+            .and_then(|config| config.serial_number.clone())
+        else {
+            return Ok(false);
+        };
+
+        let Some(name) = self
+            .ports
+            .iter()
+            .flatten()
+            .find(|port| port.serial_number.as_ref() == Some(&serial_number))
+            .map(|port| port.name.clone())
+        else {
+            return Ok(false);
+        };
+
+        let config = self.active_config_mut()?;
+        if config.port.as_ref() == Some(&name) {
+            return Ok(false);
+        }
+
+        debug!("Adapter {serial_number:?} moved to {name}");
+        config.port = Some(name);
+        self.save_config(app_handle)?;
+        Ok(true)
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.camera.is_some()
+    }
+
+    /// The port the active camera is configured to use, whether or not it is open.
+    pub fn saved_port(&self) -> Option<String> {
+        self.active_config()
+            .ok()
+            .and_then(|config| config.port.clone())
+    }
+
+    /// The active camera as configured now, so it can be reopened without the lock held.
+    pub fn reopening(&self) -> Option<Reopen> {
+        let config = self.active_config().ok()?;
+
+        Some(Reopen {
+            protocol: config.protocol,
+            port: config.port.clone()?,
+            address: config.address,
+            generation: self.generation,
+        })
+    }
+
+    /// Puts `camera`, opened from `reopen`, back in use once its port is back. Returns false,
+    /// dropping `camera`, if another camera was opened or closed while it was being opened.
+    pub fn reconnect<R: tauri::Runtime>(
+        &mut self,
+        app_handle: &tauri::AppHandle<R>,
+        reopen: &Reopen,
+        camera: Box<dyn Camera>,
+    ) -> Result<bool> {
+        if self.generation != reopen.generation || self.camera.is_some() {
+            return Ok(false);
+        }
+
+        self.install_camera(app_handle, camera);
+        self.report(StatusKind::Connection, Severity::Info, "Reconnected")?;
+        Ok(true)
+    }
+
+    /// Closes the camera after its port went away, keeping it configured so it can be reopened.
+    pub fn mark_disconnected(&mut self, reason: &str) -> Result<()> {
+        self.close_camera();
+        self.report(
+            StatusKind::Connection,
+            Severity::Error,
+            &format!("Disconnected: {reason}"),
+        )
+    }
+}
+
+/// Copies the config file aside before the defaults replace it.
+fn back_up_config<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf> {
+    let path = app.path().app_data_dir()?.join(CONFIG_FILE);
+    let backup = path.with_extension("json.bak");
+    fs::copy(&path, &backup)?;
+
+    Ok(backup)
+}
+
+fn validate_auxiliary(channel: u8) -> Result<()> {
+    if (1..=MAX_AUXILIARY).contains(&channel) {
         Ok(())
+    } else {
+        Err(Error::InvalidAuxiliary(channel))
     }
 }
 
 #[derive(Debug, Clone, Serialize, Type, Event)]
 pub struct UIStateEvent {
     port: Option<String>,
-    ports: Option<Vec<String>>,
-    status: String,
+    ports: Option<Vec<PortInfo>>,
+    cameras: Vec<CameraConfig>,
+    camera: Option<u32>,
+    protocols: Vec<Protocol>,
+    presets: Vec<PresetConfig>,
+    auxiliaries: Vec<AuxiliaryConfig>,
+    endpoints: Vec<String>,
+    power: Option<bool>,
+    tally: bool,
+    menu: bool,
+    watchdog_timeout: u32,
+    status: Status,
+    history: Vec<Status>,
 }
 
 impl UIStateEvent {
     pub fn new(state: &mut UIState) -> Self {
         Self {
-            port: state.camera().ok().and_then(|camera| camera.name()),
+            port: state.worker().ok().and_then(|worker| worker.name()),
             ports: state.ports.clone(),
+            cameras: state.config.cameras.clone(),
+            camera: state.config.active_camera,
+            protocols: Protocol::all(),
+            presets: state.config.presets.clone(),
+            auxiliaries: state.config.auxiliaries.clone(),
+            endpoints: state.config.endpoints.clone(),
+            power: state.power,
+            tally: state.tally,
+            menu: state.menu,
+            watchdog_timeout: state.config.watchdog_timeout,
             status: state.status.clone(),
+            history: state.status_history(),
         }
     }
 }
@@ -123,29 +1120,40 @@ impl TryFrom<&tauri::AppHandle> for UIStateEvent {
     }
 }
 
-pub fn with_ui_state<T, F>(app_handle: &tauri::AppHandle, func: F)
+/// Runs `func` against the UI state and emits the result. `command` names the Tauri command
+/// being handled, if any, so statuses can be traced back to it.
+pub fn with_ui_state<R, T, F>(app_handle: &tauri::AppHandle<R>, command: Option<&str>, func: F)
 where
+    R: tauri::Runtime,
     F: FnOnce(&mut UIState) -> Result<T>,
 {
     let state = app_handle.state::<Mutex<UIState>>();
     let mut state = state.lock().expect("mutext poisoned");
 
+    state.command = command.map(String::from);
     let result = func(&mut state);
 
     if let Err(error) = result {
-        let status = format!(r#"Error: {error}"#);
-        let _ = state.set_status(&status);
+        if error.is_disconnect() {
+            let _ = state.mark_disconnected(&error.to_string());
+        } else {
+            let status = format!(r#"Error: {error}"#);
+            let _ = state.report(StatusKind::Command, Severity::Error, &status);
+        }
     }
+    state.command = None;
 
     let _ = UIStateEvent::new(&mut state).emit(app_handle);
 }
 
-pub fn with_ui_state_status<T, F>(app_handle: &tauri::AppHandle, status: &str, func: F)
+/// Queues `job` on the active camera, reporting `status` once it has run.
+pub fn with_camera_status<F>(app_handle: &tauri::AppHandle, command: &str, status: &str, job: F)
 where
-    F: FnOnce(&mut UIState) -> Result<T>,
+    F: FnOnce(&mut dyn Camera) -> Result<()> + Send + 'static,
 {
-    with_ui_state(app_handle, |ui| {
-        func(ui)?;
-        ui.set_status(status)
+    let status = String::from(status);
+
+    with_ui_state(app_handle, Some(command), |ui| {
+        ui.run(app_handle, job, move |ui| ui.set_status(&status))
     })
 }
@@ -1,6 +1,9 @@
 use super::{
     Result, ViscaError,
-    packet::{RequestCategory, Response, ViscaAction, ViscaCommand, ViscaInquiry},
+    packet::{
+        RequestCategory, Response, ViscaAction, ViscaCommand, ViscaInquiry, decode_nibbles,
+        decode_signed_nibbles, encode_nibbles,
+    },
 };
 
 fn validate_speed(speed: u8, max: u8) -> Result<u8> {
@@ -32,13 +35,13 @@ impl ViscaCommand for Power {
 
 impl ViscaAction for Power {
     fn visca_action_data(&self) -> Result<Vec<u8>> {
-        Ok(vec![*self as u8])
+        encode_nibbles(*self as u32, 1)
     }
 }
 
 impl ViscaInquiry for Power {
     fn from_response(response: &Response) -> Result<Self> {
-        match response.data()[0] & 0x0F {
+        match decode_nibbles(response.data(), 1)? {
             0x02 => Ok(Self::On),
             0x03 => Ok(Self::Off),
             _ => Err(ViscaError::InvalidPowerValue),
@@ -75,7 +78,7 @@ impl ViscaCommand for Zoom {
 
 impl ViscaAction for Zoom {
     fn visca_action_data(&self) -> Result<Vec<u8>> {
-        Ok(vec![*self as u8])
+        encode_nibbles(*self as u32, 1)
     }
 }
 
@@ -98,6 +101,45 @@ impl From<Zoom> for u8 {
     }
 }
 
+/// Zooms at a chosen speed, from 1 (slowest) to 7.
+#[derive(Clone, Copy, Debug)]
+pub enum ZoomVariable {
+    Tele(u8),
+    Wide(u8),
+}
+
+impl ViscaCommand for ZoomVariable {
+    const ID: u8 = Zoom::ID;
+    const CATEGORY: RequestCategory = Zoom::CATEGORY;
+}
+
+impl ViscaAction for ZoomVariable {
+    fn visca_action_data(&self) -> Result<Vec<u8>> {
+        Ok(vec![match *self {
+            Self::Tele(speed) => 0x20 | validate_speed(speed, 0x07)?,
+            Self::Wide(speed) => 0x30 | validate_speed(speed, 0x07)?,
+        }])
+    }
+}
+
+/// CAM_Initialize: recalibrates the lens, or restarts the whole camera.
+#[derive(Clone, Copy, Debug)]
+pub enum Initialize {
+    Lens = 0x01,
+    Camera = 0x03,
+}
+
+impl ViscaCommand for Initialize {
+    const ID: u8 = 0x19;
+    const CATEGORY: RequestCategory = RequestCategory::Camera;
+}
+
+impl ViscaAction for Initialize {
+    fn visca_action_data(&self) -> Result<Vec<u8>> {
+        encode_nibbles(*self as u32, 1)
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum Autofocus {
     Auto = 0x02,
@@ -111,13 +153,13 @@ impl ViscaCommand for Autofocus {
 
 impl ViscaAction for Autofocus {
     fn visca_action_data(&self) -> Result<Vec<u8>> {
-        Ok(vec![*self as u8])
+        encode_nibbles(*self as u32, 1)
     }
 }
 
 impl ViscaInquiry for Autofocus {
     fn from_response(response: &Response) -> Result<Self> {
-        match response.data()[0] & 0x0F {
+        match decode_nibbles(response.data(), 1)? {
             0x02 => Ok(Self::Auto),
             0x03 => Ok(Self::Manual),
             _ => Err(ViscaError::InvalidAutofocusValue),
@@ -158,7 +200,7 @@ impl ViscaCommand for Focus {
 
 impl ViscaAction for Focus {
     fn visca_action_data(&self) -> Result<Vec<u8>> {
-        Ok(vec![*self as u8])
+        encode_nibbles(*self as u32, 1)
     }
 }
 
@@ -175,6 +217,68 @@ impl TryFrom<&str> for Focus {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ZoomPosition(pub u16);
+
+impl ViscaCommand for ZoomPosition {
+    const ID: u8 = 0x47;
+    const CATEGORY: RequestCategory = RequestCategory::Camera;
+}
+
+impl ViscaAction for ZoomPosition {
+    fn visca_action_data(&self) -> Result<Vec<u8>> {
+        encode_nibbles(self.0.into(), 4)
+    }
+}
+
+impl ViscaInquiry for ZoomPosition {
+    fn from_response(response: &Response) -> Result<Self> {
+        Ok(Self(decode_nibbles(response.data(), 4)? as u16))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FocusPosition(pub u16);
+
+impl ViscaCommand for FocusPosition {
+    const ID: u8 = 0x48;
+    const CATEGORY: RequestCategory = RequestCategory::Camera;
+}
+
+impl ViscaAction for FocusPosition {
+    fn visca_action_data(&self) -> Result<Vec<u8>> {
+        encode_nibbles(self.0.into(), 4)
+    }
+}
+
+impl ViscaInquiry for FocusPosition {
+    fn from_response(response: &Response) -> Result<Self> {
+        Ok(Self(decode_nibbles(response.data(), 4)? as u16))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IrisPosition(pub u8);
+
+impl ViscaCommand for IrisPosition {
+    const ID: u8 = 0x4B;
+    const CATEGORY: RequestCategory = RequestCategory::Camera;
+}
+
+impl ViscaAction for IrisPosition {
+    fn visca_action_data(&self) -> Result<Vec<u8>> {
+        encode_nibbles(self.0.into(), 4)
+    }
+}
+
+impl ViscaInquiry for IrisPosition {
+    fn from_response(response: &Response) -> Result<Self> {
+        u8::try_from(decode_nibbles(response.data(), 4)?)
+            .map(Self)
+            .map_err(|_| ViscaError::InvalidResponse)
+    }
+}
+
 #[derive(Clone, Copy)]
 pub enum Preset {
     Set(u8),
@@ -200,6 +304,108 @@ impl ViscaAction for Preset {
     }
 }
 
+/// Sets the speed the camera uses when recalling `preset`, from 0x01 (slowest) to 0x18.
+#[derive(Clone, Copy, Debug)]
+pub struct PresetSpeed {
+    pub preset: u8,
+    pub speed: u8,
+}
+
+impl PresetSpeed {
+    pub const MAX: u8 = 0x18;
+}
+
+impl ViscaCommand for PresetSpeed {
+    const ID: u8 = 0x01;
+    const CATEGORY: RequestCategory = RequestCategory::Extension;
+}
+
+impl ViscaAction for PresetSpeed {
+    fn visca_action_data(&self) -> Result<Vec<u8>> {
+        Ok(vec![
+            0x0B,
+            validate_preset(self.preset)?,
+            validate_speed(self.speed, Self::MAX)?,
+        ])
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Tally {
+    On = 0x02,
+    Off = 0x03,
+}
+
+impl ViscaCommand for Tally {
+    const ID: u8 = 0x01;
+    const CATEGORY: RequestCategory = RequestCategory::Extension;
+}
+
+impl ViscaAction for Tally {
+    fn visca_action_data(&self) -> Result<Vec<u8>> {
+        let mut data = vec![0x0A, 0x00];
+        data.extend(encode_nibbles(*self as u32, 1)?);
+        Ok(data)
+    }
+}
+
+impl From<bool> for Tally {
+    fn from(value: bool) -> Self {
+        if value { Self::On } else { Self::Off }
+    }
+}
+
+/// IR_Receive Return: whether the camera reports IR remote presses, which it does with
+/// `y0 07 7D 01 cc ii FF` notifications.
+#[derive(Clone, Copy, Debug)]
+pub enum IrReceiveReturn {
+    On = 0x03,
+    Off = 0x13,
+}
+
+impl ViscaCommand for IrReceiveReturn {
+    const ID: u8 = 0x01;
+    const CATEGORY: RequestCategory = RequestCategory::Remote;
+}
+
+impl ViscaAction for IrReceiveReturn {
+    fn visca_action_data(&self) -> Result<Vec<u8>> {
+        Ok(vec![*self as u8, 0x00])
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Menu {
+    On = 0x02,
+    Off = 0x03,
+}
+
+impl ViscaCommand for Menu {
+    const ID: u8 = 0x06;
+    const CATEGORY: RequestCategory = RequestCategory::PanTilt;
+}
+
+impl ViscaAction for Menu {
+    fn visca_action_data(&self) -> Result<Vec<u8>> {
+        Ok(vec![*self as u8])
+    }
+}
+
+/// Selects the highlighted item in the on-screen menu.
+#[derive(Clone, Copy, Debug)]
+pub struct MenuEnter;
+
+impl ViscaCommand for MenuEnter {
+    const ID: u8 = 0x01;
+    const CATEGORY: RequestCategory = RequestCategory::Extension;
+}
+
+impl ViscaAction for MenuEnter {
+    fn visca_action_data(&self) -> Result<Vec<u8>> {
+        Ok(vec![0x02, 0x00, 0x01])
+    }
+}
+
 pub enum Move {
     Up(u8),
     Down(u8),
@@ -238,6 +444,31 @@ impl ViscaAction for Move {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PanTiltPosition {
+    pub pan: i16,
+    pub tilt: i16,
+}
+
+impl ViscaCommand for PanTiltPosition {
+    const ID: u8 = 0x12;
+    const CATEGORY: RequestCategory = RequestCategory::PanTilt;
+}
+
+impl ViscaInquiry for PanTiltPosition {
+    fn from_response(response: &Response) -> Result<Self> {
+        let data = response.data();
+        if data.len() != 8 {
+            return Err(ViscaError::InvalidResponse);
+        }
+
+        Ok(Self {
+            pan: decode_signed_nibbles(&data[0..4], 4)? as i16,
+            tilt: decode_signed_nibbles(&data[4..8], 4)? as i16,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -272,6 +503,8 @@ mod tests {
     #[test_case(b"\x90\x50\x02\xFF" => matches Ok(Power::On); "on")]
     #[test_case(b"\x90\x50\x03\xFF" => matches Ok(Power::Off); "off")]
     #[test_case(b"\x90\x50\x00\xFF" => matches Err(ViscaError::InvalidPowerValue); "invalid power value")]
+    #[test_case(b"\x90\x50\xFF" => matches Err(ViscaError::InvalidResponse); "empty payload")]
+    #[test_case(b"\x90\x50\x02\x02\xFF" => matches Err(ViscaError::InvalidResponse); "long payload")]
     fn test_power_inquiry_from_response_payload(payload: &'static [u8]) -> Result<Power> {
         let (_, response) = Response::from_bytes((payload, 0))?;
         Power::from_response(&response)
@@ -305,6 +538,22 @@ mod tests {
         Zoom::try_from(value)
     }
 
+    #[test_case(ZoomVariable::Tele(0x01) => using matches_bytes(b"\x81\x01\x04\x07\x21\xFF"); "tele slow")]
+    #[test_case(ZoomVariable::Wide(0x07) => using matches_bytes(b"\x81\x01\x04\x07\x37\xFF"); "wide fast")]
+    #[test_case(ZoomVariable::Tele(0x00) => matches Err(ViscaError::InvalidSpeed); "speed too low")]
+    #[test_case(ZoomVariable::Wide(0x08) => matches Err(ViscaError::InvalidSpeed); "speed too high")]
+    fn test_zoom_variable_to_bytes(command: ZoomVariable) -> Result<Vec<u8>> {
+        let bytes = command.action(1).build()?.to_bytes()?;
+        Ok(bytes)
+    }
+
+    #[test_case(Initialize::Lens => using matches_bytes(b"\x81\x01\x04\x19\x01\xFF"); "lens")]
+    #[test_case(Initialize::Camera => using matches_bytes(b"\x81\x01\x04\x19\x03\xFF"); "camera")]
+    fn test_initialize_to_bytes(command: Initialize) -> Result<Vec<u8>> {
+        let bytes = command.action(1).build()?.to_bytes()?;
+        Ok(bytes)
+    }
+
     #[test_case(Autofocus::Auto => using matches_bytes(b"\x81\x01\x04\x38\x02\xFF"); "auto")]
     #[test_case(Autofocus::Manual => using matches_bytes(b"\x81\x01\x04\x38\x03\xFF"); "manual")]
     fn test_autofocus_to_bytes(autofocus: Autofocus) -> Result<Vec<u8>> {
@@ -329,6 +578,7 @@ mod tests {
         b"\x90\x50\x00\xFF" => matches Err(ViscaError::InvalidAutofocusValue);
         "invalid autofocus value"
     )]
+    #[test_case(b"\x90\x50\xFF" => matches Err(ViscaError::InvalidResponse); "empty payload")]
     fn test_autofocus_from_response_payload(payload: &'static [u8]) -> Result<Autofocus> {
         let (_, response) = Response::from_bytes((payload, 0))?;
         Autofocus::from_response(&response)
@@ -362,6 +612,77 @@ mod tests {
         Focus::try_from(value)
     }
 
+    #[test_case(ZoomPosition(0x0000) => using matches_bytes(b"\x81\x01\x04\x47\x00\x00\x00\x00\xFF"); "wide end")]
+    #[test_case(ZoomPosition(0x4000) => using matches_bytes(b"\x81\x01\x04\x47\x04\x00\x00\x00\xFF"); "tele end")]
+    fn test_zoom_position_to_bytes(position: ZoomPosition) -> Result<Vec<u8>> {
+        let bytes = position.action(1).build()?.to_bytes()?;
+        Ok(bytes)
+    }
+
+    #[test_case(b"\x90\x50\x01\x02\x03\x04\xFF" => matches Ok(ZoomPosition(0x1234)); "position")]
+    #[test_case(b"\x90\x50\xFF" => matches Err(ViscaError::InvalidResponse); "empty payload")]
+    #[test_case(b"\x90\x50\x01\x02\x03\xFF" => matches Err(ViscaError::InvalidResponse); "short payload")]
+    #[test_case(b"\x90\x50\x01\x02\x13\x04\xFF" => matches Err(ViscaError::InvalidResponse); "not nibbles")]
+    fn test_zoom_position_from_response_payload(payload: &'static [u8]) -> Result<ZoomPosition> {
+        let (_, response) = Response::from_bytes((payload, 0))?;
+        ZoomPosition::from_response(&response)
+    }
+
+    #[test_case(FocusPosition(0x1000) => using matches_bytes(b"\x81\x01\x04\x48\x01\x00\x00\x00\xFF"); "focus")]
+    fn test_focus_position_to_bytes(position: FocusPosition) -> Result<Vec<u8>> {
+        let bytes = position.action(1).build()?.to_bytes()?;
+        Ok(bytes)
+    }
+
+    #[test_case(b"\x90\x50\x0A\x0B\x0C\x0D\xFF" => matches Ok(FocusPosition(0xABCD)); "position")]
+    #[test_case(b"\x90\x50\xFF" => matches Err(ViscaError::InvalidResponse); "empty payload")]
+    fn test_focus_position_from_response_payload(payload: &'static [u8]) -> Result<FocusPosition> {
+        let (_, response) = Response::from_bytes((payload, 0))?;
+        FocusPosition::from_response(&response)
+    }
+
+    #[test_case(IrisPosition(0x11) => using matches_bytes(b"\x81\x01\x04\x4B\x00\x00\x01\x01\xFF"); "iris")]
+    fn test_iris_position_to_bytes(position: IrisPosition) -> Result<Vec<u8>> {
+        let bytes = position.action(1).build()?.to_bytes()?;
+        Ok(bytes)
+    }
+
+    #[test_case(b"\x90\x50\x00\x00\x01\x01\xFF" => matches Ok(IrisPosition(0x11)); "position")]
+    #[test_case(b"\x90\x50\x00\x01\x01\x01\xFF" => matches Err(ViscaError::InvalidResponse); "out of range")]
+    #[test_case(b"\x90\x50\xFF" => matches Err(ViscaError::InvalidResponse); "empty payload")]
+    fn test_iris_position_from_response_payload(payload: &'static [u8]) -> Result<IrisPosition> {
+        let (_, response) = Response::from_bytes((payload, 0))?;
+        IrisPosition::from_response(&response)
+    }
+
+    #[test]
+    fn test_pan_tilt_position_inquiry_to_bytes() -> Result<()> {
+        assert_eq!(
+            InquiryRequestBuilder::new(1)
+                .build::<PanTiltPosition>()?
+                .to_bytes(),
+            Ok(b"\x81\x09\x06\x12\xFF".into())
+        );
+        Ok(())
+    }
+
+    #[test_case(
+        b"\x90\x50\x00\x09\x09\x00\x0F\x0F\x0F\x0F\xFF"
+            => matches Ok(PanTiltPosition { pan: 0x0990, tilt: -1 });
+        "position"
+    )]
+    #[test_case(
+        b"\x90\x50\x00\x09\x09\x00\xFF" => matches Err(ViscaError::InvalidResponse);
+        "short payload"
+    )]
+    #[test_case(b"\x90\x50\xFF" => matches Err(ViscaError::InvalidResponse); "empty payload")]
+    fn test_pan_tilt_position_from_response_payload(
+        payload: &'static [u8],
+    ) -> Result<PanTiltPosition> {
+        let (_, response) = Response::from_bytes((payload, 0))?;
+        PanTiltPosition::from_response(&response)
+    }
+
     #[test_case(Preset::Set(3) => using matches_bytes(b"\x81\x01\x04\x3F\x01\x03\xFF"); "set 3")]
     #[test_case(Preset::Set(4) => using matches_bytes(b"\x81\x01\x04\x3F\x01\x04\xFF"); "set 4")]
     #[test_case(Preset::Set(0x10) => matches Err(ViscaError::InvalidPreset); "set invalid preset")]
@@ -373,6 +694,58 @@ mod tests {
         Ok(bytes)
     }
 
+    #[test_case(
+        PresetSpeed { preset: 3, speed: 0x01 } => using matches_bytes(b"\x81\x01\x7E\x01\x0B\x03\x01\xFF");
+        "preset 3 slowest"
+    )]
+    #[test_case(
+        PresetSpeed { preset: 4, speed: 0x18 } => using matches_bytes(b"\x81\x01\x7E\x01\x0B\x04\x18\xFF");
+        "preset 4 fastest"
+    )]
+    #[test_case(PresetSpeed { preset: 4, speed: 0x00 } => matches Err(ViscaError::InvalidSpeed); "speed low")]
+    #[test_case(PresetSpeed { preset: 4, speed: 0x19 } => matches Err(ViscaError::InvalidSpeed); "speed high")]
+    #[test_case(PresetSpeed { preset: 0x10, speed: 0x01 } => matches Err(ViscaError::InvalidPreset); "invalid preset")]
+    fn test_preset_speed_to_bytes(command: PresetSpeed) -> Result<Vec<u8>> {
+        let bytes = command.action(1).build()?.to_bytes()?;
+        Ok(bytes)
+    }
+
+    #[test_case(Tally::On => using matches_bytes(b"\x81\x01\x7E\x01\x0A\x00\x02\xFF"); "on")]
+    #[test_case(Tally::Off => using matches_bytes(b"\x81\x01\x7E\x01\x0A\x00\x03\xFF"); "off")]
+    fn test_tally_to_bytes(command: Tally) -> Result<Vec<u8>> {
+        let bytes = command.action(1).build()?.to_bytes()?;
+        Ok(bytes)
+    }
+
+    #[test_case(true => matches Tally::On; "on")]
+    #[test_case(false => matches Tally::Off; "off")]
+    fn test_tally_from_bool(value: bool) -> Tally {
+        Tally::from(value)
+    }
+
+    #[test_case(IrReceiveReturn::On => using matches_bytes(b"\x81\x01\x7D\x01\x03\x00\xFF"); "on")]
+    #[test_case(IrReceiveReturn::Off => using matches_bytes(b"\x81\x01\x7D\x01\x13\x00\xFF"); "off")]
+    fn test_ir_receive_return_to_bytes(command: IrReceiveReturn) -> Result<Vec<u8>> {
+        let bytes = command.action(1).build()?.to_bytes()?;
+        Ok(bytes)
+    }
+
+    #[test_case(Menu::On => using matches_bytes(b"\x81\x01\x06\x06\x02\xFF"); "on")]
+    #[test_case(Menu::Off => using matches_bytes(b"\x81\x01\x06\x06\x03\xFF"); "off")]
+    fn test_menu_to_bytes(command: Menu) -> Result<Vec<u8>> {
+        let bytes = command.action(1).build()?.to_bytes()?;
+        Ok(bytes)
+    }
+
+    #[test]
+    fn test_menu_enter_to_bytes() -> Result<()> {
+        assert_eq!(
+            MenuEnter.action(1).build()?.to_bytes(),
+            Ok(b"\x81\x01\x7E\x01\x02\x00\x01\xFF".into())
+        );
+        Ok(())
+    }
+
     #[test_case(Move::Up(0x01) => using matches_bytes(b"\x81\x01\x06\x01\x00\x01\x03\x01\xFF"); "up 1")]
     #[test_case(Move::Up(0x14) => using matches_bytes(b"\x81\x01\x06\x01\x00\x14\x03\x01\xFF"); "up 20")]
     #[test_case(Move::Up(0x00) => matches Err(ViscaError::InvalidSpeed); "up invalid speed low")]
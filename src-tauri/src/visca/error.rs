@@ -1,5 +1,7 @@
 use thiserror::Error as ThisError;
 
+use super::ResponseErrorKind;
+
 #[derive(ThisError, Debug)]
 pub enum ViscaError {
     #[error("invalid power value")]
@@ -23,6 +25,9 @@ pub enum ViscaError {
     #[error("invalid preset")]
     InvalidPreset,
 
+    #[error("value out of range")]
+    ValueOutOfRange,
+
     #[error("invalid message length")]
     InvalidMessageLength,
 
@@ -50,10 +55,26 @@ pub enum ViscaError {
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
+    #[error(transparent)]
+    SerialPort(#[from] serialport::Error),
+
     #[error(transparent)]
     Deku(#[from] deku::DekuError),
 }
 
+impl From<ResponseErrorKind> for ViscaError {
+    fn from(value: ResponseErrorKind) -> Self {
+        match value {
+            ResponseErrorKind::InvalidMessageLength => Self::InvalidMessageLength,
+            ResponseErrorKind::Syntax => Self::Syntax,
+            ResponseErrorKind::CommandBufferFull => Self::CommandBufferFull,
+            ResponseErrorKind::CommandCanceled => Self::CommandCanceled,
+            ResponseErrorKind::NoSocket => Self::NoSocket,
+            ResponseErrorKind::CommandNotExecutable => Self::CommandNotExecutable,
+        }
+    }
+}
+
 impl serde::Serialize for ViscaError {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
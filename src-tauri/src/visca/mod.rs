@@ -2,8 +2,10 @@ mod commands;
 mod error;
 mod packet;
 mod port;
+mod simulator;
 
 pub use commands::*;
 pub use error::*;
 pub use packet::*;
 pub use port::*;
+pub use simulator::*;
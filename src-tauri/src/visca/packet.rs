@@ -1,6 +1,63 @@
-use super::Result;
+use super::{Result, ViscaError};
 use deku::prelude::*;
 
+fn nibble_bits(count: usize) -> Result<u32> {
+    if count > 0 && count <= 8 {
+        Ok(count as u32 * 4)
+    } else {
+        Err(ViscaError::ValueOutOfRange)
+    }
+}
+
+/// Splits `value` into `count` nibbles (`0p 0q 0r 0s`), most significant first.
+pub fn encode_nibbles(value: u32, count: usize) -> Result<Vec<u8>> {
+    let bits = nibble_bits(count)?;
+
+    if bits < 32 && value >> bits != 0 {
+        return Err(ViscaError::ValueOutOfRange);
+    }
+
+    Ok((0..count)
+        .rev()
+        .map(|index| ((value >> (index * 4)) & 0x0F) as u8)
+        .collect())
+}
+
+/// Splits `value` into `count` nibbles using two's complement, most significant first.
+pub fn encode_signed_nibbles(value: i32, count: usize) -> Result<Vec<u8>> {
+    let bits = nibble_bits(count)?;
+
+    if bits < 32 {
+        let limit = 1i64 << (bits - 1);
+        if i64::from(value) < -limit || i64::from(value) >= limit {
+            return Err(ViscaError::ValueOutOfRange);
+        }
+    }
+
+    encode_nibbles(value as u32 & (u32::MAX >> (32 - bits)), count)
+}
+
+/// Joins exactly `count` nibbles from `data` into a single value.
+pub fn decode_nibbles(data: &[u8], count: usize) -> Result<u32> {
+    nibble_bits(count)?;
+
+    if data.len() != count || data.iter().any(|nibble| *nibble > 0x0F) {
+        return Err(ViscaError::InvalidResponse);
+    }
+
+    Ok(data
+        .iter()
+        .fold(0, |value, nibble| (value << 4) | u32::from(*nibble)))
+}
+
+/// Joins exactly `count` nibbles from `data` into a sign-extended value.
+pub fn decode_signed_nibbles(data: &[u8], count: usize) -> Result<i32> {
+    let value = decode_nibbles(data, count)?;
+    let shift = 32 - nibble_bits(count)?;
+
+    Ok(((value << shift) as i32) >> shift)
+}
+
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
 pub struct Header {
     #[deku(bits = 1)]
@@ -31,7 +88,7 @@ impl Header {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, DekuRead)]
+#[derive(Debug, Copy, Clone, PartialEq, DekuRead, DekuWrite)]
 #[deku(id_type = "u8")]
 pub enum ResponseErrorKind {
     #[deku(id = 0x01)]
@@ -53,7 +110,7 @@ pub enum ResponseErrorKind {
     CommandNotExecutable,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, DekuRead)]
+#[derive(Debug, Copy, Clone, PartialEq, DekuRead, DekuWrite)]
 #[deku(id_type = "u8", bits = 3)]
 pub enum ResponseKind {
     #[deku(id = 0x4)]
@@ -66,7 +123,7 @@ pub enum ResponseKind {
     Err(#[deku(bits = 4)] u8, ResponseErrorKind),
 }
 
-#[derive(Debug, PartialEq, DekuRead)]
+#[derive(Debug, PartialEq, DekuRead, DekuWrite)]
 pub struct Response {
     header: Header,
 
@@ -78,6 +135,32 @@ pub struct Response {
 }
 
 impl Response {
+    fn new(sender: u8, kind: ResponseKind, mut data: Vec<u8>) -> Self {
+        data.push(0xFF);
+
+        Self {
+            header: Header::new(sender, 0),
+            kind,
+            data,
+        }
+    }
+
+    pub fn ack(sender: u8, socket: u8) -> Self {
+        Self::new(sender, ResponseKind::Ack(socket), vec![])
+    }
+
+    pub fn completion(sender: u8, socket: u8, data: Vec<u8>) -> Self {
+        Self::new(sender, ResponseKind::Completion(socket), data)
+    }
+
+    pub fn error(sender: u8, socket: u8, kind: ResponseErrorKind) -> Self {
+        Self::new(sender, ResponseKind::Err(socket, kind), vec![])
+    }
+
+    pub fn sender(&self) -> u8 {
+        self.header.sender()
+    }
+
     pub fn kind(&self) -> ResponseKind {
         self.kind
     }
@@ -87,7 +170,52 @@ impl Response {
     }
 }
 
-#[derive(Debug, PartialEq, DekuWrite)]
+/// A frame the camera sends on its own rather than in reply to a request.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Notification {
+    /// `z0 38 FF`: the camera's address was reassigned.
+    NetworkChange { sender: u8 },
+
+    /// `y0 07 7D 01 cc ii FF`: a command was issued from the IR remote.
+    IrReceive { sender: u8, category: u8, id: u8 },
+}
+
+impl Notification {
+    fn from_frame(frame: &[u8]) -> Option<Self> {
+        let sender = (*frame.first()? >> 4) & 0x07;
+
+        match frame.get(1..)? {
+            [0x38, 0xFF] => Some(Self::NetworkChange { sender }),
+            [0x07, 0x7D, 0x01, category, id, 0xFF] => Some(Self::IrReceive {
+                sender,
+                category: *category,
+                id: *id,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Message {
+    Response(Response),
+    Notification(Notification),
+}
+
+impl TryFrom<&[u8]> for Message {
+    type Error = ViscaError;
+
+    fn try_from(frame: &[u8]) -> Result<Self> {
+        if let Some(notification) = Notification::from_frame(frame) {
+            return Ok(Self::Notification(notification));
+        }
+
+        let (_, response) = Response::from_bytes((frame, 0))?;
+        Ok(Self::Response(response))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, DekuWrite)]
 #[deku(id_type = "u8")]
 pub enum RequestKind {
     #[deku(id = "0x01")]
@@ -96,13 +224,28 @@ pub enum RequestKind {
     Inquiry,
 }
 
-#[derive(Debug, PartialEq, DekuWrite)]
+#[derive(Debug, Copy, Clone, PartialEq, DekuWrite)]
 #[deku(id_type = "u8")]
+#[repr(u8)]
 pub enum RequestCategory {
-    #[deku(id = "0x04")]
-    Camera,
-    #[deku(id = "0x06")]
-    PanTilt,
+    Camera = 0x04,
+    PanTilt = 0x06,
+    Remote = 0x7D,
+    Extension = 0x7E,
+}
+
+impl TryFrom<u8> for RequestCategory {
+    type Error = ViscaError;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0x04 => Ok(Self::Camera),
+            0x06 => Ok(Self::PanTilt),
+            0x7D => Ok(Self::Remote),
+            0x7E => Ok(Self::Extension),
+            _ => Err(ViscaError::Syntax),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, DekuWrite)]
@@ -134,6 +277,56 @@ impl Request {
             last: 0xFF,
         }
     }
+
+    pub fn sender(&self) -> u8 {
+        self.header.sender()
+    }
+
+    pub fn receiver(&self) -> u8 {
+        self.header.receiver()
+    }
+
+    pub fn kind(&self) -> RequestKind {
+        self.kind
+    }
+
+    pub fn category(&self) -> RequestCategory {
+        self.category
+    }
+
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl TryFrom<&[u8]> for Request {
+    type Error = ViscaError;
+
+    fn try_from(frame: &[u8]) -> Result<Self> {
+        let [header, kind, category, id, data @ .., 0xFF] = frame else {
+            return Err(ViscaError::InvalidMessageLength);
+        };
+
+        let (_, header) = Header::from_bytes((&[*header], 0))?;
+        let kind = match kind {
+            0x01 => RequestKind::Command,
+            0x09 => RequestKind::Inquiry,
+            _ => return Err(ViscaError::Syntax),
+        };
+
+        Ok(Self::new(
+            header.sender(),
+            header.receiver(),
+            kind,
+            RequestCategory::try_from(*category)?,
+            *id,
+            data.to_vec(),
+        ))
+    }
 }
 
 pub struct ActionRequestBuilder<A: ViscaAction> {
@@ -384,4 +577,109 @@ mod tests {
         let result = data.build()?.to_bytes()?;
         Ok(result)
     }
+
+    #[test_case(0x0, 1 => using matches_bytes(b"\x00"); "zero")]
+    #[test_case(0x3, 1 => using matches_bytes(b"\x03"); "single nibble")]
+    #[test_case(0x4000, 4 => using matches_bytes(b"\x04\x00\x00\x00"); "four nibbles")]
+    #[test_case(0x1234, 4 => using matches_bytes(b"\x01\x02\x03\x04"); "ordering")]
+    #[test_case(0x10, 1 => matches Err(ViscaError::ValueOutOfRange); "overflow")]
+    #[test_case(0x0, 0 => matches Err(ViscaError::ValueOutOfRange); "no nibbles")]
+    #[test_case(0x0, 9 => matches Err(ViscaError::ValueOutOfRange); "too many nibbles")]
+    fn test_encode_nibbles(value: u32, count: usize) -> Result<Vec<u8>> {
+        encode_nibbles(value, count)
+    }
+
+    #[test_case(0, 4 => using matches_bytes(b"\x00\x00\x00\x00"); "zero")]
+    #[test_case(0x0990, 4 => using matches_bytes(b"\x00\x09\x09\x00"); "positive")]
+    #[test_case(-1, 4 => using matches_bytes(b"\x0F\x0F\x0F\x0F"); "minus one")]
+    #[test_case(-0x0990, 4 => using matches_bytes(b"\x0F\x06\x07\x00"); "negative")]
+    #[test_case(0x8000, 4 => matches Err(ViscaError::ValueOutOfRange); "positive overflow")]
+    #[test_case(-0x8001, 4 => matches Err(ViscaError::ValueOutOfRange); "negative overflow")]
+    fn test_encode_signed_nibbles(value: i32, count: usize) -> Result<Vec<u8>> {
+        encode_signed_nibbles(value, count)
+    }
+
+    #[test_case(b"\x02", 1 => matches Ok(0x2); "single nibble")]
+    #[test_case(b"\x04\x00\x00\x00", 4 => matches Ok(0x4000); "four nibbles")]
+    #[test_case(b"", 1 => matches Err(ViscaError::InvalidResponse); "empty")]
+    #[test_case(b"\x01\x02", 1 => matches Err(ViscaError::InvalidResponse); "too long")]
+    #[test_case(b"\x01\x02", 3 => matches Err(ViscaError::InvalidResponse); "too short")]
+    #[test_case(b"\x10", 1 => matches Err(ViscaError::InvalidResponse); "not a nibble")]
+    fn test_decode_nibbles(data: &'static [u8], count: usize) -> Result<u32> {
+        decode_nibbles(data, count)
+    }
+
+    #[test_case(b"\x00\x09\x09\x00", 4 => matches Ok(0x0990); "positive")]
+    #[test_case(b"\x0F\x0F\x0F\x0F", 4 => matches Ok(-1); "minus one")]
+    #[test_case(b"\x0F\x06\x07\x00", 4 => matches Ok(-0x0990); "negative")]
+    #[test_case(b"\x0F\x0F", 4 => matches Err(ViscaError::InvalidResponse); "too short")]
+    fn test_decode_signed_nibbles(data: &'static [u8], count: usize) -> Result<i32> {
+        decode_signed_nibbles(data, count)
+    }
+
+    #[test_case(b"\x90\x38\xFF" => matches Ok(Message::Notification(Notification::NetworkChange {
+        sender: 1
+    })); "network change")]
+    #[test_case(b"\xA0\x07\x7D\x01\x04\x00\xFF" => matches Ok(Message::Notification(
+        Notification::IrReceive { sender: 2, category: 0x04, id: 0x00 }
+    )); "ir receive")]
+    #[test_case(b"\x90\x41\xFF" => matches Ok(Message::Response(Response {
+        kind: ResponseKind::Ack(1),
+        ..
+    })); "ack")]
+    #[test_case(b"\x90\x50\x02\xFF" => matches Ok(Message::Response(Response {
+        kind: ResponseKind::Completion(0),
+        ..
+    })); "completion")]
+    #[test_case(b"\x90\x38\x01\xFF" => matches Err(_); "garbage")]
+    fn test_message_try_from(frame: &'static [u8]) -> Result<Message> {
+        Message::try_from(frame)
+    }
+
+    #[test_case(Response::ack(1, 1) => using matches_bytes(b"\x90\x41\xFF"); "ack")]
+    #[test_case(Response::completion(1, 2, vec![]) => using matches_bytes(b"\x90\x52\xFF"); "completion")]
+    #[test_case(
+        Response::completion(2, 0, vec![0x02]) => using matches_bytes(b"\xA0\x50\x02\xFF");
+        "completion with data"
+    )]
+    #[test_case(
+        Response::error(1, 1, ResponseErrorKind::CommandNotExecutable)
+            => using matches_bytes(b"\x90\x61\x41\xFF");
+        "error"
+    )]
+    fn test_response_to_bytes(response: Response) -> Result<Vec<u8>> {
+        let result = response.to_bytes()?;
+        Ok(result)
+    }
+
+    #[test_case(b"\x81\x01\x04\x00\x02\xFF" => matches Ok(Request {
+        kind: RequestKind::Command,
+        category: RequestCategory::Camera,
+        id: 0x00,
+        ..
+    }); "command")]
+    #[test_case(b"\x81\x09\x06\x12\xFF" => matches Ok(Request {
+        kind: RequestKind::Inquiry,
+        category: RequestCategory::PanTilt,
+        id: 0x12,
+        ..
+    }); "inquiry")]
+    #[test_case(b"\x81\x01\x04\xFF" => matches Err(ViscaError::InvalidMessageLength); "too short")]
+    #[test_case(b"\x81\x01\x04\x00\x02" => matches Err(ViscaError::InvalidMessageLength); "unterminated")]
+    #[test_case(b"\x81\x02\x04\x00\xFF" => matches Err(ViscaError::Syntax); "unknown kind")]
+    #[test_case(b"\x81\x01\x05\x00\xFF" => matches Err(ViscaError::Syntax); "unknown category")]
+    fn test_request_try_from(frame: &'static [u8]) -> Result<Request> {
+        Request::try_from(frame)
+    }
+
+    #[test]
+    fn test_request_round_trip() -> Result<()> {
+        let bytes = b"\x81\x01\x04\x47\x01\x02\x03\x04\xFF";
+        let request = Request::try_from(&bytes[..])?;
+
+        assert_eq!(request.receiver(), 1);
+        assert_eq!(request.data(), b"\x01\x02\x03\x04");
+        assert_eq!(request.to_bytes()?, bytes);
+        Ok(())
+    }
 }
@@ -1,13 +1,20 @@
-use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::{
+    io::{BufRead, BufReader, ErrorKind, Write},
+    sync::{
+        Arc,
+        atomic::{AtomicU8, Ordering},
+        mpsc::{Receiver, Sender, channel},
+    },
+};
 
-use deku::{DekuContainerRead, DekuContainerWrite};
+use deku::DekuContainerWrite;
 use log::debug;
-use serialport::SerialPort;
 
 use super::{
-    InquiryRequestBuilder, Request, Response, ResponseKind, Result, ViscaAction, ViscaError,
-    ViscaInquiry,
+    InquiryRequestBuilder, Message, Notification, Request, Response, ResponseErrorKind,
+    ResponseKind, Result, ViscaAction, ViscaError, ViscaInquiry,
 };
+use crate::transport::Transport;
 
 fn header_for_address(address: u8) -> Result<u8> {
     if address <= 7 {
@@ -17,20 +24,67 @@ fn header_for_address(address: u8) -> Result<u8> {
     }
 }
 
-pub struct ViscaPort {
-    reader: BufReader<Box<dyn SerialPort>>,
-    writer: Box<dyn SerialPort>,
+/// Cancels the command a [`ViscaPort`] is waiting on from another thread, writing through a
+/// second handle to the transport so it doesn't need the port's lock.
+pub struct ViscaCanceller {
+    writer: Box<dyn Write + Send>,
+    address: u8,
+    pending: Arc<AtomicU8>,
+}
+
+impl ViscaCanceller {
+    /// Sends `8x 2p FF` for the socket the pending command is running in, and returns whether
+    /// there was one to cancel.
+    pub fn cancel(&mut self) -> Result<bool> {
+        let socket = self.pending.load(Ordering::SeqCst);
+        if socket == 0 {
+            return Ok(false);
+        }
+
+        let output = [header_for_address(self.address)?, 0x20 | socket, 0xFF];
+
+        #[cfg(debug_assertions)]
+        debug!("Canceling: {:02X?}", output);
+
+        self.writer.write_all(&output)?;
+        Ok(true)
+    }
+}
+
+pub struct ViscaPort<T: Transport> {
+    reader: BufReader<T>,
+    notifications: Option<Sender<Notification>>,
+    /// The socket of a command the camera has acknowledged but not yet completed, or 0.
+    pending: Arc<AtomicU8>,
 }
 
-impl ViscaPort {
-    pub fn new(port: Box<dyn SerialPort>) -> Self {
+impl<T: Transport> ViscaPort<T> {
+    pub fn new(transport: T) -> Self {
         Self {
-            writer: port.try_clone().unwrap(),
-            reader: BufReader::new(port),
+            reader: BufReader::new(transport),
+            notifications: None,
+            pending: Arc::new(AtomicU8::new(0)),
         }
     }
 
-    fn send_packet_with_response(&mut self, address: u8, request: &Request) -> Result<Response> {
+    /// Returns a [`ViscaCanceller`] for commands sent to the camera at `address`.
+    pub fn canceller(&self, address: u8) -> Result<ViscaCanceller> {
+        Ok(ViscaCanceller {
+            writer: self.reader.get_ref().try_clone_writer()?,
+            address,
+            pending: Arc::clone(&self.pending),
+        })
+    }
+
+    /// Returns a channel that receives every unsolicited frame read from the port. Calling this
+    /// again replaces the previous channel.
+    pub fn notifications(&mut self) -> Receiver<Notification> {
+        let (sender, receiver) = channel();
+        self.notifications = Some(sender);
+        receiver
+    }
+
+    fn send_packet_with_response(&mut self, _address: u8, request: &Request) -> Result<Response> {
         let output: Vec<u8> = request.to_bytes()?;
 
         #[cfg(debug_assertions)]
@@ -38,22 +92,35 @@ impl ViscaPort {
             debug!("Sending: {:02X?}", output);
         }
 
-        self.writer.write_all(&output)?;
+        self.reader.get_mut().write_all(&output)?;
 
         let response = self.receive_response()?;
-        if let ResponseKind::Completion(_) = response.kind() {
-            return Ok(response);
+        match response.kind() {
+            ResponseKind::Completion(_) => return Ok(response),
+            ResponseKind::Err(_, kind) => return Err(kind.into()),
+            ResponseKind::Ack(socket) => self.pending.store(socket, Ordering::SeqCst),
         }
 
-        let response = self.receive_response()?;
-        if let ResponseKind::Completion(_) = response.kind() {
-            Ok(response)
-        } else {
-            Err(ViscaError::InvalidResponse)
+        let response = self.receive_response();
+        self.pending.store(0, Ordering::SeqCst);
+
+        let response = response?;
+        match response.kind() {
+            ResponseKind::Completion(_) => Ok(response),
+            ResponseKind::Err(_, kind) => Err(kind.into()),
+            ResponseKind::Ack(_) => Err(ViscaError::InvalidResponse),
         }
     }
 
-    fn receive_response(&mut self) -> Result<Response> {
+    fn dispatch(&self, notification: Notification) {
+        debug!("Notification: {notification:?}");
+
+        if let Some(sender) = &self.notifications {
+            let _ = sender.send(notification);
+        }
+    }
+
+    fn receive_message(&mut self) -> Result<Message> {
         loop {
             let mut bytes: Vec<u8> = Vec::with_capacity(16);
             match self.reader.read_until(0xFF, &mut bytes) {
@@ -61,8 +128,7 @@ impl ViscaPort {
                     #[cfg(debug_assertions)]
                     debug!("Received: {:02X?}", bytes.to_vec());
 
-                    let ((_, _), response) = Response::from_bytes((bytes.as_ref(), 0))?;
-                    return Ok(response);
+                    return Message::try_from(bytes.as_ref());
                 }
                 Err(error) if error.kind() == ErrorKind::Interrupted => continue,
                 Err(error) => return Err(ViscaError::Io(error)),
@@ -70,6 +136,73 @@ impl ViscaPort {
         }
     }
 
+    fn receive_response(&mut self) -> Result<Response> {
+        loop {
+            match self.receive_message()? {
+                // Only a cancel that lost the race with a completion is answered this way
+                Message::Response(response)
+                    if matches!(
+                        response.kind(),
+                        ResponseKind::Err(_, ResponseErrorKind::NoSocket)
+                    ) =>
+                {
+                    debug!("Discarding late cancel reply: {response:?}");
+                }
+                Message::Response(response) => return Ok(response),
+                Message::Notification(notification) => self.dispatch(notification),
+            }
+        }
+    }
+
+    /// Writes `bytes` as-is and collects the frames read back until the camera completes, fails,
+    /// or stops answering. Unsolicited frames are dispatched as usual rather than returned.
+    pub fn send_raw(&mut self, bytes: &[u8]) -> Result<Vec<u8>> {
+        #[cfg(debug_assertions)]
+        debug!("Sending raw: {:02X?}", bytes);
+
+        self.reader.get_mut().write_all(bytes)?;
+
+        let mut received: Vec<u8> = Vec::new();
+        loop {
+            let mut frame: Vec<u8> = Vec::with_capacity(16);
+            match self.reader.read_until(0xFF, &mut frame) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) if error.kind() == ErrorKind::TimedOut => {
+                    received.extend(frame);
+                    break;
+                }
+                Err(error) => return Err(ViscaError::Io(error)),
+            }
+
+            match Message::try_from(frame.as_ref()) {
+                Ok(Message::Notification(notification)) => self.dispatch(notification),
+                Ok(Message::Response(response)) => {
+                    received.extend(frame);
+                    if !matches!(response.kind(), ResponseKind::Ack(_)) {
+                        break;
+                    }
+                }
+                Err(_) => received.extend(frame),
+            }
+        }
+
+        Ok(received)
+    }
+
+    /// Drains any frames the camera sent while no command was pending.
+    pub fn poll(&mut self) -> Result<()> {
+        while !self.reader.buffer().is_empty() || self.reader.get_ref().bytes_to_read()? > 0 {
+            match self.receive_message()? {
+                Message::Notification(notification) => self.dispatch(notification),
+                Message::Response(response) => debug!("Discarding stray response: {response:?}"),
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn execute(&mut self, address: u8, action: impl ViscaAction) -> Result<()> {
         let request = action.action(address).build()?;
         let response = self.send_packet_with_response(address, &request)?;
@@ -0,0 +1,926 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, ErrorKind, Read, Write},
+    sync::{Arc, Condvar, Mutex, Weak},
+    thread,
+    time::Duration,
+};
+
+use deku::DekuContainerWrite;
+use log::debug;
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+
+use super::{
+    Autofocus, Focus, FocusPosition, Initialize, IrReceiveReturn, Menu, Move, PanTiltPosition,
+    Power, Preset, PresetSpeed, Request, RequestCategory, RequestKind, Response, ResponseErrorKind,
+    ViscaCommand, Zoom, ZoomPosition, decode_nibbles, encode_nibbles, encode_signed_nibbles,
+};
+
+/// The port name the settings window shows for the simulated camera.
+pub const SIMULATOR_PORT: &str = "VISCA Simulator";
+
+const ZOOM_MAX: u16 = 0x4000;
+const ZOOM_STEP: u16 = 0x0400;
+const FOCUS_MAX: u16 = 0xF000;
+const FOCUS_STEP: u16 = 0x0800;
+const PAN_LIMIT: i16 = 0x0990;
+const TILT_LIMIT: i16 = 0x0510;
+const MOVE_STEP: i16 = 0x10;
+
+#[derive(Default)]
+struct Pipe {
+    buffer: Mutex<VecDeque<u8>>,
+    ready: Condvar,
+}
+
+/// One end of an in-memory serial link. Bytes written to one end of a pair are read from the
+/// other, so a [`ViscaSimulator`] can stand in for a camera wherever a `SerialPort` is expected.
+pub struct VirtualPort {
+    name: String,
+    incoming: Arc<Pipe>,
+    outgoing: Arc<Pipe>,
+    timeout: Duration,
+    alive: Arc<()>,
+    peer: Weak<()>,
+}
+
+impl VirtualPort {
+    pub fn pair(name: &str) -> (Self, Self) {
+        let left = Arc::new(Pipe::default());
+        let right = Arc::new(Pipe::default());
+        let left_alive = Arc::new(());
+        let right_alive = Arc::new(());
+
+        (
+            Self {
+                name: name.to_string(),
+                incoming: left.clone(),
+                outgoing: right.clone(),
+                timeout: Duration::from_secs(1),
+                peer: Arc::downgrade(&right_alive),
+                alive: left_alive.clone(),
+            },
+            Self {
+                name: name.to_string(),
+                incoming: right,
+                outgoing: left,
+                timeout: Duration::from_secs(1),
+                peer: Arc::downgrade(&left_alive),
+                alive: right_alive,
+            },
+        )
+    }
+
+    /// Whether any handle to the other end of the pair still exists.
+    pub fn is_peer_connected(&self) -> bool {
+        self.peer.strong_count() > 0
+    }
+}
+
+impl Read for VirtualPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let buffer = self.incoming.buffer.lock().expect("mutex poisoned");
+        let (mut buffer, _) = self
+            .incoming
+            .ready
+            .wait_timeout_while(buffer, self.timeout, |buffer| buffer.is_empty())
+            .expect("mutex poisoned");
+
+        if buffer.is_empty() {
+            if !self.is_peer_connected() {
+                return Ok(0);
+            }
+            return Err(io::Error::new(ErrorKind::TimedOut, "Operation timed out"));
+        }
+
+        let count = buf.len().min(buffer.len());
+        for (byte, value) in buf.iter_mut().zip(buffer.drain(..count)) {
+            *byte = value;
+        }
+
+        Ok(count)
+    }
+}
+
+impl Write for VirtualPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.is_peer_connected() {
+            return Err(io::Error::from(ErrorKind::BrokenPipe));
+        }
+
+        self.outgoing
+            .buffer
+            .lock()
+            .expect("mutex poisoned")
+            .extend(buf);
+        self.outgoing.ready.notify_all();
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SerialPort for VirtualPort {
+    fn name(&self) -> Option<String> {
+        Some(self.name.clone())
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        Ok(9600)
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Ok(DataBits::Eight)
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Ok(FlowControl::None)
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        Ok(Parity::None)
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Ok(StopBits::One)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_baud_rate(&mut self, _baud_rate: u32) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, _data_bits: DataBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, _flow_control: FlowControl) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_parity(&mut self, _parity: Parity) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, _stop_bits: StopBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, _level: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, _level: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        let buffer = self.incoming.buffer.lock().expect("mutex poisoned");
+        Ok(buffer.len() as u32)
+    }
+
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        Ok(0)
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        if let ClearBuffer::Input | ClearBuffer::All = buffer_to_clear {
+            self.incoming.buffer.lock().expect("mutex poisoned").clear();
+        }
+        Ok(())
+    }
+
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        Ok(Box::new(Self {
+            name: self.name.clone(),
+            incoming: self.incoming.clone(),
+            outgoing: self.outgoing.clone(),
+            timeout: self.timeout,
+            alive: self.alive.clone(),
+            peer: self.peer.clone(),
+        }))
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub pan: i16,
+    pub tilt: i16,
+    pub zoom: u16,
+    pub focus: u16,
+}
+
+/// Where the simulated head and lens start, and return to when the camera is initialized.
+const HOME: Position = Position {
+    pan: 0,
+    tilt: 0,
+    zoom: 0,
+    focus: 0x1000,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct SimulatorState {
+    pub power: Power,
+    pub autofocus: Autofocus,
+    pub tally: bool,
+    pub menu: bool,
+    /// Whether IR remote presses are reported back, as IR_Receive Return turns on.
+    pub ir_receive_return: bool,
+    pub position: Position,
+}
+
+/// A VISCA device that answers [`Request`]s the way a camera would, replying with
+/// Ack/Completion/Error [`Response`]s and keeping track of power, lens and pan/tilt state.
+pub struct ViscaSimulator {
+    address: u8,
+    state: SimulatorState,
+    presets: HashMap<u8, Position>,
+    preset_speeds: HashMap<u8, u8>,
+}
+
+impl ViscaSimulator {
+    pub fn new(address: u8) -> Self {
+        Self {
+            address,
+            state: SimulatorState {
+                power: Power::On,
+                autofocus: Autofocus::Auto,
+                tally: false,
+                menu: false,
+                ir_receive_return: false,
+                position: HOME,
+            },
+            presets: HashMap::new(),
+            preset_speeds: HashMap::new(),
+        }
+    }
+
+    pub fn state(&self) -> &SimulatorState {
+        &self.state
+    }
+
+    pub fn preset_speed(&self, preset: u8) -> Option<u8> {
+        self.preset_speeds.get(&preset).copied()
+    }
+
+    /// Handles a single frame and returns the frames the camera would send back.
+    pub fn handle(&mut self, frame: &[u8]) -> Vec<Response> {
+        // `8x 2p FF` cancels the command in socket p, but commands here complete before a
+        // cancel could arrive, so there is never one to cancel
+        if let [header, cancel @ 0x20..=0x2F, 0xFF] = frame {
+            if header & 0x07 != self.address {
+                return vec![];
+            }
+            return vec![Response::error(
+                self.address,
+                cancel & 0x0F,
+                ResponseErrorKind::NoSocket,
+            )];
+        }
+
+        let request = match Request::try_from(frame) {
+            Ok(request) => request,
+            Err(_) => {
+                return vec![Response::error(self.address, 0, ResponseErrorKind::Syntax)];
+            }
+        };
+
+        if request.receiver() != self.address {
+            return vec![];
+        }
+
+        match request.kind() {
+            RequestKind::Command => match self.execute(&request) {
+                Ok(()) => vec![
+                    Response::ack(self.address, 1),
+                    Response::completion(self.address, 1, vec![]),
+                ],
+                Err(ResponseErrorKind::CommandNotExecutable) => vec![
+                    Response::ack(self.address, 1),
+                    Response::error(self.address, 1, ResponseErrorKind::CommandNotExecutable),
+                ],
+                Err(kind) => vec![Response::error(self.address, 0, kind)],
+            },
+            RequestKind::Inquiry => match self.inquire(&request) {
+                Ok(data) => vec![Response::completion(self.address, 0, data)],
+                Err(kind) => vec![Response::error(self.address, 0, kind)],
+            },
+        }
+    }
+
+    fn execute(&mut self, request: &Request) -> Result<(), ResponseErrorKind> {
+        let data = request.data();
+        let position = &mut self.state.position;
+
+        // Only power and IR_Receive Return work in standby
+        if matches!(self.state.power, Power::Off)
+            && !matches!(
+                (request.category(), request.id()),
+                (Power::CATEGORY, Power::ID) | (IrReceiveReturn::CATEGORY, IrReceiveReturn::ID)
+            )
+        {
+            return Err(ResponseErrorKind::CommandNotExecutable);
+        }
+
+        match (request.category(), request.id()) {
+            (RequestCategory::Camera, Power::ID) => {
+                self.state.power = match nibble(data)? {
+                    0x02 => Power::On,
+                    0x03 => Power::Off,
+                    _ => return Err(ResponseErrorKind::Syntax),
+                };
+            }
+            (RequestCategory::Camera, Initialize::ID) => match nibble(data)? {
+                0x01 => {
+                    position.zoom = HOME.zoom;
+                    position.focus = HOME.focus;
+                }
+                0x03 => {
+                    *position = HOME;
+                    self.state.autofocus = Autofocus::Auto;
+                    self.state.menu = false;
+                }
+                _ => return Err(ResponseErrorKind::Syntax),
+            },
+            (RequestCategory::Camera, Autofocus::ID) => {
+                self.state.autofocus = match nibble(data)? {
+                    0x02 => Autofocus::Auto,
+                    0x03 => Autofocus::Manual,
+                    _ => return Err(ResponseErrorKind::Syntax),
+                };
+            }
+            (RequestCategory::Camera, Zoom::ID) => match data {
+                [0x00] => {}
+                [0x02] => position.zoom = position.zoom.saturating_add(ZOOM_STEP).min(ZOOM_MAX),
+                [0x03] => position.zoom = position.zoom.saturating_sub(ZOOM_STEP),
+                // Variable speed zooms step further the faster they go
+                [speed @ 0x20..=0x27] => {
+                    let step = ZOOM_STEP / 4 * u16::from(speed & 0x0F);
+                    position.zoom = position.zoom.saturating_add(step).min(ZOOM_MAX);
+                }
+                [speed @ 0x30..=0x37] => {
+                    position.zoom = position
+                        .zoom
+                        .saturating_sub(ZOOM_STEP / 4 * u16::from(speed & 0x0F));
+                }
+                _ => return Err(ResponseErrorKind::Syntax),
+            },
+            (RequestCategory::Camera, ZoomPosition::ID) => {
+                position.zoom = position_nibbles(data)?.min(ZOOM_MAX);
+            }
+            (RequestCategory::Camera, Focus::ID) => match nibble(data)? {
+                0x00 => {}
+                0x02 => position.focus = position.focus.saturating_add(FOCUS_STEP).min(FOCUS_MAX),
+                0x03 => position.focus = position.focus.saturating_sub(FOCUS_STEP),
+                _ => return Err(ResponseErrorKind::Syntax),
+            },
+            (RequestCategory::Camera, FocusPosition::ID) => {
+                position.focus = position_nibbles(data)?.min(FOCUS_MAX);
+            }
+            (RequestCategory::Camera, Preset::ID) => match data {
+                [0x00, preset] => {
+                    self.presets.remove(preset);
+                }
+                [0x01, preset] => {
+                    self.presets.insert(*preset, *position);
+                }
+                [0x02, preset] => {
+                    *position = *self
+                        .presets
+                        .get(preset)
+                        .ok_or(ResponseErrorKind::CommandNotExecutable)?;
+                }
+                _ => return Err(ResponseErrorKind::Syntax),
+            },
+            (PresetSpeed::CATEGORY, PresetSpeed::ID) => match data {
+                [0x0B, preset, speed] if (0x01..=PresetSpeed::MAX).contains(speed) => {
+                    self.preset_speeds.insert(*preset, *speed);
+                }
+                [0x02, 0x00, 0x01] if self.state.menu => {}
+                [0x0A, 0x00, 0x02] => self.state.tally = true,
+                [0x0A, 0x00, 0x03] => self.state.tally = false,
+                _ => return Err(ResponseErrorKind::Syntax),
+            },
+            (IrReceiveReturn::CATEGORY, IrReceiveReturn::ID) => match data {
+                [0x03, 0x00] => self.state.ir_receive_return = true,
+                [0x13, 0x00] => self.state.ir_receive_return = false,
+                _ => return Err(ResponseErrorKind::Syntax),
+            },
+            (RequestCategory::PanTilt, Menu::ID) => match data {
+                [0x02] => self.state.menu = true,
+                [0x03] => self.state.menu = false,
+                [0x10] => self.state.menu = !self.state.menu,
+                _ => return Err(ResponseErrorKind::Syntax),
+            },
+            // While the menu is open, pan/tilt drive moves the highlight instead of the head
+            (RequestCategory::PanTilt, Move::ID) if self.state.menu => {}
+            (RequestCategory::PanTilt, Move::ID) => match data {
+                [pan_speed, tilt_speed, pan, tilt] => {
+                    let pan_step = MOVE_STEP * i16::from(*pan_speed);
+                    let tilt_step = MOVE_STEP * i16::from(*tilt_speed);
+
+                    position.pan = match pan {
+                        0x01 => position.pan - pan_step,
+                        0x02 => position.pan + pan_step,
+                        0x03 => position.pan,
+                        _ => return Err(ResponseErrorKind::Syntax),
+                    }
+                    .clamp(-PAN_LIMIT, PAN_LIMIT);
+
+                    position.tilt = match tilt {
+                        0x01 => position.tilt + tilt_step,
+                        0x02 => position.tilt - tilt_step,
+                        0x03 => position.tilt,
+                        _ => return Err(ResponseErrorKind::Syntax),
+                    }
+                    .clamp(-TILT_LIMIT, TILT_LIMIT);
+                }
+                _ => return Err(ResponseErrorKind::InvalidMessageLength),
+            },
+            _ => return Err(ResponseErrorKind::Syntax),
+        }
+
+        Ok(())
+    }
+
+    fn inquire(&self, request: &Request) -> Result<Vec<u8>, ResponseErrorKind> {
+        let position = &self.state.position;
+
+        if !request.data().is_empty() {
+            return Err(ResponseErrorKind::InvalidMessageLength);
+        }
+
+        let data = match (request.category(), request.id()) {
+            (RequestCategory::Camera, Power::ID) => encode_nibbles(self.state.power as u32, 1),
+            (RequestCategory::Camera, Autofocus::ID) => {
+                encode_nibbles(self.state.autofocus as u32, 1)
+            }
+            (RequestCategory::Camera, ZoomPosition::ID) => encode_nibbles(position.zoom.into(), 4),
+            (RequestCategory::Camera, FocusPosition::ID) => {
+                encode_nibbles(position.focus.into(), 4)
+            }
+            (PanTiltPosition::CATEGORY, PanTiltPosition::ID) => {
+                encode_signed_nibbles(position.pan.into(), 4).and_then(|mut pan| {
+                    pan.extend(encode_signed_nibbles(position.tilt.into(), 4)?);
+                    Ok(pan)
+                })
+            }
+            _ => return Err(ResponseErrorKind::Syntax),
+        };
+
+        data.map_err(|_| ResponseErrorKind::CommandNotExecutable)
+    }
+
+    /// Serves requests read from `io` until it reaches end of file or fails.
+    pub fn run<T: Read + Write>(&mut self, mut io: T) -> io::Result<()> {
+        let mut frame: Vec<u8> = Vec::with_capacity(16);
+        let mut byte = [0u8; 1];
+
+        loop {
+            match io.read(&mut byte) {
+                Ok(0) => return Ok(()),
+                Ok(_) => {}
+                Err(error)
+                    if matches!(error.kind(), ErrorKind::TimedOut | ErrorKind::Interrupted) =>
+                {
+                    continue;
+                }
+                Err(error) => return Err(error),
+            }
+
+            frame.push(byte[0]);
+            if byte[0] != 0xFF {
+                continue;
+            }
+
+            debug!("Simulator received: {:02X?}", frame);
+
+            for response in self.handle(&frame) {
+                io.write_all(&response.to_bytes().map_err(io::Error::other)?)?;
+            }
+
+            frame.clear();
+        }
+    }
+
+    /// Runs the simulator on a background thread and returns the port a driver talks to.
+    pub fn spawn(mut self) -> VirtualPort {
+        let (client, device) = VirtualPort::pair(SIMULATOR_PORT);
+
+        thread::spawn(move || {
+            if let Err(error) = self.run(device) {
+                debug!("Simulator stopped: {error}");
+            }
+        });
+
+        client
+    }
+
+    /// Runs the simulator behind a pseudo-terminal so tools outside the app can reach it through
+    /// the returned port's device path.
+    #[cfg(unix)]
+    pub fn spawn_pty(mut self) -> serialport::Result<serialport::TTYPort> {
+        let (mut device, client) = serialport::TTYPort::pair()?;
+        device.set_timeout(Duration::from_secs(1))?;
+
+        thread::spawn(move || {
+            if let Err(error) = self.run(device) {
+                debug!("Simulator stopped: {error}");
+            }
+        });
+
+        Ok(client)
+    }
+}
+
+fn nibble(data: &[u8]) -> Result<u8, ResponseErrorKind> {
+    decode_nibbles(data, 1)
+        .map(|value| value as u8)
+        .map_err(|_| ResponseErrorKind::Syntax)
+}
+
+fn position_nibbles(data: &[u8]) -> Result<u16, ResponseErrorKind> {
+    decode_nibbles(data, 4)
+        .map(|value| value as u16)
+        .map_err(|_| ResponseErrorKind::Syntax)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::transport::{SerialTransport, TcpTransport, Transport};
+    use crate::visca::{
+        InquiryRequestBuilder, ResponseKind, Tally, ViscaAction, ViscaError, ViscaInquiry,
+        ViscaPort,
+    };
+    use test_case::test_case;
+
+    fn send(simulator: &mut ViscaSimulator, action: impl ViscaAction) -> Vec<ResponseKind> {
+        let frame = action.action(1).build().unwrap().to_bytes().unwrap();
+        simulator
+            .handle(&frame)
+            .iter()
+            .map(|response| response.kind())
+            .collect()
+    }
+
+    fn inquire<I: ViscaInquiry>(simulator: &mut ViscaSimulator) -> I {
+        let frame = InquiryRequestBuilder::new(1)
+            .build::<I>()
+            .unwrap()
+            .to_bytes()
+            .unwrap();
+        let responses = simulator.handle(&frame);
+        I::from_response(&responses[0]).unwrap()
+    }
+
+    #[test]
+    fn test_command_replies_with_ack_and_completion() {
+        let mut simulator = ViscaSimulator::new(1);
+
+        assert_eq!(
+            send(&mut simulator, Zoom::Tele),
+            vec![ResponseKind::Ack(1), ResponseKind::Completion(1)]
+        );
+        assert_eq!(simulator.state().position.zoom, ZOOM_STEP);
+    }
+
+    #[test_case(b"\x81\x01\x04\xFF"; "too short")]
+    #[test_case(b"\x81\x01\x05\x00\x02\xFF"; "unknown category")]
+    #[test_case(b"\x81\x01\x04\x7F\x02\xFF"; "unknown command")]
+    fn test_invalid_request_replies_with_syntax_error(frame: &'static [u8]) {
+        let mut simulator = ViscaSimulator::new(1);
+
+        assert_eq!(
+            simulator
+                .handle(frame)
+                .iter()
+                .map(|response| response.kind())
+                .collect::<Vec<_>>(),
+            vec![ResponseKind::Err(0, ResponseErrorKind::Syntax)]
+        );
+    }
+
+    #[test]
+    fn test_cancel_without_pending_command() {
+        let mut simulator = ViscaSimulator::new(1);
+
+        assert_eq!(
+            simulator
+                .handle(b"\x81\x21\xFF")
+                .iter()
+                .map(|response| response.kind())
+                .collect::<Vec<_>>(),
+            vec![ResponseKind::Err(1, ResponseErrorKind::NoSocket)]
+        );
+        assert!(simulator.handle(b"\x82\x21\xFF").is_empty());
+    }
+
+    #[test]
+    fn test_ignores_other_addresses() {
+        let mut simulator = ViscaSimulator::new(2);
+
+        assert!(send(&mut simulator, Zoom::Tele).is_empty());
+    }
+
+    #[test]
+    fn test_commands_fail_while_powered_off() {
+        let mut simulator = ViscaSimulator::new(1);
+
+        send(&mut simulator, Power::Off);
+        assert_eq!(
+            send(&mut simulator, Zoom::Tele),
+            vec![
+                ResponseKind::Ack(1),
+                ResponseKind::Err(1, ResponseErrorKind::CommandNotExecutable)
+            ]
+        );
+
+        send(&mut simulator, Power::On);
+        assert!(matches!(inquire::<Power>(&mut simulator), Power::On));
+    }
+
+    #[test]
+    fn test_presets_restore_position() {
+        let mut simulator = ViscaSimulator::new(1);
+
+        send(&mut simulator, Move::Right(0x10));
+        send(&mut simulator, ZoomPosition(0x2000));
+        send(&mut simulator, Preset::Set(3));
+        send(&mut simulator, Move::Down(0x10));
+        send(&mut simulator, Zoom::Wide);
+        send(&mut simulator, Preset::Recall(3));
+
+        assert_eq!(
+            inquire::<PanTiltPosition>(&mut simulator),
+            PanTiltPosition {
+                pan: 0x100,
+                tilt: 0
+            }
+        );
+        assert_eq!(
+            inquire::<ZoomPosition>(&mut simulator),
+            ZoomPosition(0x2000)
+        );
+    }
+
+    #[test]
+    fn test_variable_zoom() {
+        let mut simulator = ViscaSimulator::new(1);
+
+        send(&mut simulator, crate::visca::ZoomVariable::Tele(0x07));
+        assert_eq!(simulator.state().position.zoom, ZOOM_STEP / 4 * 7);
+
+        send(&mut simulator, crate::visca::ZoomVariable::Wide(0x01));
+        assert_eq!(simulator.state().position.zoom, ZOOM_STEP / 4 * 6);
+    }
+
+    #[test]
+    fn test_initialize() {
+        let mut simulator = ViscaSimulator::new(1);
+
+        send(&mut simulator, Move::Right(0x10));
+        send(&mut simulator, ZoomPosition(0x2000));
+        send(&mut simulator, Autofocus::Manual);
+        let pan = simulator.state().position.pan;
+
+        send(&mut simulator, Initialize::Lens);
+        assert_eq!(simulator.state().position.zoom, 0);
+        assert_eq!(simulator.state().position.pan, pan);
+        assert_ne!(pan, 0);
+
+        send(&mut simulator, Initialize::Camera);
+        assert_eq!(simulator.state().position, HOME);
+        assert!(matches!(simulator.state().autofocus, Autofocus::Auto));
+    }
+
+    #[test]
+    fn test_preset_speed() {
+        let mut simulator = ViscaSimulator::new(1);
+
+        send(
+            &mut simulator,
+            PresetSpeed {
+                preset: 2,
+                speed: 0x05,
+            },
+        );
+
+        assert_eq!(simulator.preset_speed(2), Some(0x05));
+        assert_eq!(simulator.preset_speed(3), None);
+    }
+
+    #[test]
+    fn test_tally() {
+        let mut simulator = ViscaSimulator::new(1);
+
+        send(&mut simulator, Tally::On);
+        assert!(simulator.state().tally);
+
+        send(&mut simulator, Tally::Off);
+        assert!(!simulator.state().tally);
+    }
+
+    #[test]
+    fn test_ir_receive_return_works_in_standby() {
+        let mut simulator = ViscaSimulator::new(1);
+        send(&mut simulator, Power::Off);
+
+        assert!(matches!(
+            send(&mut simulator, IrReceiveReturn::On)[..],
+            [ResponseKind::Ack(_), ResponseKind::Completion(_)]
+        ));
+        assert!(simulator.state().ir_receive_return);
+    }
+
+    #[test]
+    fn test_opening_camera_turns_on_ir_receive_return() -> crate::error::Result<()> {
+        use crate::camera::Camera;
+        use crate::visca_camera::ViscaCamera;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?.to_string();
+        let simulator = thread::spawn(move || {
+            let (stream, _) = listener.accept()?;
+            let mut simulator = ViscaSimulator::new(1);
+            simulator.run(stream)?;
+            io::Result::Ok(simulator.state().ir_receive_return)
+        });
+
+        drop(ViscaCamera::<TcpTransport>::new(&address, 1)?);
+
+        assert!(simulator.join().unwrap()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_menu_suspends_pan_tilt() {
+        let mut simulator = ViscaSimulator::new(1);
+
+        send(&mut simulator, Menu::On);
+        send(&mut simulator, Move::Left(0x10));
+        assert_eq!(
+            send(&mut simulator, crate::visca::MenuEnter),
+            vec![ResponseKind::Ack(1), ResponseKind::Completion(1)]
+        );
+        assert_eq!(simulator.state().position.pan, 0);
+
+        send(&mut simulator, Menu::Off);
+        assert!(!simulator.state().menu);
+        assert_eq!(
+            send(&mut simulator, crate::visca::MenuEnter),
+            vec![ResponseKind::Err(0, ResponseErrorKind::Syntax)]
+        );
+    }
+
+    #[test]
+    fn test_recalling_unknown_preset_fails() {
+        let mut simulator = ViscaSimulator::new(1);
+
+        assert_eq!(
+            send(&mut simulator, Preset::Recall(5)),
+            vec![
+                ResponseKind::Ack(1),
+                ResponseKind::Err(1, ResponseErrorKind::CommandNotExecutable)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_port_against_simulator() -> crate::visca::Result<()> {
+        let mut port = ViscaPort::new(SerialTransport::new(Box::new(
+            ViscaSimulator::new(1).spawn(),
+        )));
+
+        port.execute(1, Power::Off)?;
+        assert!(matches!(port.inquire::<Power>(1)?, Power::Off));
+        assert!(matches!(
+            port.execute(1, Zoom::Tele),
+            Err(ViscaError::CommandNotExecutable)
+        ));
+
+        port.execute(1, Power::On)?;
+        port.execute(1, FocusPosition(0x2000))?;
+        assert_eq!(port.inquire::<FocusPosition>(1)?, FocusPosition(0x2000));
+        Ok(())
+    }
+
+    #[test]
+    fn test_port_ignores_late_cancel_reply() -> crate::visca::Result<()> {
+        let mut port = ViscaPort::new(SerialTransport::new(Box::new(
+            ViscaSimulator::new(1).spawn(),
+        )));
+
+        assert_eq!(port.send_raw(b"\x81\x21\xFF")?, b"\x90\x61\x05\xFF");
+        port.send_raw(b"\x81\x21\xFF")?;
+        port.execute(1, Zoom::Tele)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_cancel_pending_command() -> crate::visca::Result<()> {
+        let (client, mut device) = VirtualPort::pair("Cancel");
+        let mut port = ViscaPort::new(SerialTransport::new(Box::new(client)));
+        let mut canceller = port.canceller(1)?;
+        assert!(!canceller.cancel()?);
+
+        let recall = thread::spawn(move || port.execute(1, Preset::Recall(1)));
+
+        // Acknowledge the recall but never complete it, as a camera still moving would
+        let mut frame = [0u8; 7];
+        device.read_exact(&mut frame)?;
+        device.write_all(&Response::ack(1, 2).to_bytes()?)?;
+
+        while !canceller.cancel()? {
+            thread::sleep(Duration::from_millis(1));
+        }
+        let mut cancel = [0u8; 3];
+        device.read_exact(&mut cancel)?;
+        assert_eq!(cancel, [0x81, 0x22, 0xFF]);
+        device.write_all(&Response::error(1, 2, ResponseErrorKind::CommandCanceled).to_bytes()?)?;
+
+        assert!(matches!(
+            recall.join().unwrap(),
+            Err(ViscaError::CommandCanceled)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_send_raw_against_simulator() -> crate::visca::Result<()> {
+        let mut port = ViscaPort::new(SerialTransport::new(Box::new(
+            ViscaSimulator::new(1).spawn(),
+        )));
+
+        assert_eq!(
+            port.send_raw(b"\x81\x01\x04\x00\x03\xFF")?,
+            b"\x90\x41\xFF\x90\x51\xFF"
+        );
+        assert_eq!(port.send_raw(b"\x81\x09\x04\x00\xFF")?, b"\x90\x50\x03\xFF");
+        assert_eq!(port.send_raw(b"\x81\x01\x7F\xFF")?, b"\x90\x60\x02\xFF");
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_port_against_pty_simulator() -> crate::visca::Result<()> {
+        let client = ViscaSimulator::new(1).spawn_pty()?;
+        let mut port = ViscaPort::new(SerialTransport::new(Box::new(client)));
+
+        port.execute(1, Autofocus::Manual)?;
+        assert!(matches!(port.inquire::<Autofocus>(1)?, Autofocus::Manual));
+        Ok(())
+    }
+
+    #[test]
+    fn test_port_against_tcp_simulator() -> crate::visca::Result<()> {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?.to_string();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept()?;
+            ViscaSimulator::new(1).run(stream)
+        });
+
+        let mut port = ViscaPort::new(TcpTransport::open(&address, 9600)?);
+
+        port.execute(1, ZoomPosition(0x1000))?;
+        assert_eq!(port.inquire::<ZoomPosition>(1)?, ZoomPosition(0x1000));
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+use std::{
+    sync::{Arc, Mutex, Weak, mpsc::Receiver},
+    thread,
+    time::Duration,
+};
+
+use log::debug;
+
+use crate::camera::{
+    Camera, CameraEvent, Direction, EventHandler, Interrupter, RawReply, ResetKind, Speed,
+    format_hex,
+};
+use crate::error::{Error, Result};
+use crate::transport::{SerialTransport, Transport};
+use crate::visca::{
+    Autofocus, Focus, Initialize, IrReceiveReturn, Menu, MenuEnter, Message, Move, Notification,
+    Power, Preset, PresetSpeed, Tally, ViscaAction, ViscaCommand, ViscaPort, ViscaSimulator, Zoom,
+    ZoomVariable,
+};
+
+const BAUD_RATE: u32 = 9600;
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Pan speeds run from 0x01 to 0x18 and tilt speeds from 0x01 to 0x14.
+fn pan_tilt_speeds(speed: Speed) -> (u8, u8) {
+    match speed {
+        Speed::Slow => (0x02, 0x02),
+        Speed::Medium => (0x04, 0x04),
+        Speed::Fast => (0x10, 0x0E),
+        Speed::Turbo => (0x18, 0x14),
+    }
+}
+
+/// Variable zoom speeds run from 0x01 to 0x07.
+fn zoom_speed(speed: Speed) -> u8 {
+    match speed {
+        Speed::Slow => 0x01,
+        Speed::Medium => 0x03,
+        Speed::Fast => 0x05,
+        Speed::Turbo => 0x07,
+    }
+}
+
+pub struct ViscaCamera<T: Transport = SerialTransport> {
+    name: Option<String>,
+    address: u8,
+    port: Arc<Mutex<ViscaPort<T>>>,
+    notifications: Option<Receiver<Notification>>,
+}
+
+impl ViscaCamera<SerialTransport> {
+    /// Connects to a [`ViscaSimulator`] instead of real hardware.
+    pub fn simulated(address: u8) -> Self {
+        Self::from_transport(
+            SerialTransport::new(Box::new(ViscaSimulator::new(address).spawn())),
+            address,
+        )
+    }
+}
+
+impl<T: Transport + 'static> ViscaCamera<T> {
+    fn from_transport(transport: T, address: u8) -> Self {
+        let name = transport.name();
+        let mut port = ViscaPort::new(transport);
+        let notifications = port.notifications();
+
+        // Cameras only report IR remote presses, such as power, once asked to
+        if let Err(error) = port.execute(address, IrReceiveReturn::On) {
+            debug!("Turning on IR_Receive Return failed: {error}");
+        }
+        let port = Arc::new(Mutex::new(port));
+
+        Self::spawn_poller(Arc::downgrade(&port));
+
+        Self {
+            name,
+            address,
+            port,
+            notifications: Some(notifications),
+        }
+    }
+
+    fn execute(&mut self, action: impl ViscaAction) -> Result<()> {
+        let mut port = self.port.lock().expect("mutex poisoned");
+        Ok(port.execute(self.address, action)?)
+    }
+
+    fn spawn_poller(port: Weak<Mutex<ViscaPort<T>>>) {
+        thread::spawn(move || {
+            while let Some(port) = port.upgrade() {
+                if let Err(error) = port.lock().expect("mutex poisoned").poll() {
+                    debug!("VISCA poll failed: {error}");
+                }
+                drop(port);
+
+                thread::sleep(POLL_INTERVAL);
+            }
+        });
+    }
+
+    fn spawn_forwarder(
+        port: Weak<Mutex<ViscaPort<T>>>,
+        address: u8,
+        notifications: Receiver<Notification>,
+        handler: EventHandler,
+    ) {
+        thread::spawn(move || {
+            // The port owns the sender, so this ends once the camera is dropped
+            for notification in notifications {
+                let event = match notification {
+                    Notification::NetworkChange { .. } => Some(CameraEvent::NetworkChange),
+                    Notification::IrReceive { category, id, .. }
+                        if category == Power::CATEGORY as u8 && id == Power::ID =>
+                    {
+                        // The remote only says the power button was pressed, so ask the camera
+                        // which way it went. The port lock is released before the handler runs.
+                        port.upgrade().and_then(|port| {
+                            let mut port = port.lock().expect("mutex poisoned");
+                            port.inquire::<Power>(address)
+                                .ok()
+                                .map(|power| CameraEvent::Power(power.into()))
+                        })
+                    }
+                    Notification::IrReceive { .. } => None,
+                };
+
+                if let Some(event) = event {
+                    handler(event);
+                }
+            }
+        });
+    }
+}
+
+impl<T: Transport + 'static> Camera for ViscaCamera<T> {
+    fn new(path: &str, address: u8) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self::from_transport(T::open(path, BAUD_RATE)?, address))
+    }
+
+    fn name(&self) -> Option<String> {
+        self.name.clone()
+    }
+
+    fn power_on(&mut self) -> Result<()> {
+        self.execute(Power::On)
+    }
+
+    fn power_off(&mut self) -> Result<()> {
+        self.execute(Power::Off)
+    }
+
+    fn autofocus(&mut self, state: bool) -> Result<()> {
+        self.execute(Autofocus::from(state))
+    }
+
+    fn zoom_in(&mut self, speed: Speed) -> Result<()> {
+        self.execute(ZoomVariable::Tele(zoom_speed(speed)))
+    }
+
+    fn zoom_out(&mut self, speed: Speed) -> Result<()> {
+        self.execute(ZoomVariable::Wide(zoom_speed(speed)))
+    }
+
+    fn pan_tilt(&mut self, direction: Direction, speed: Speed) -> Result<()> {
+        let (pan_speed, tilt_speed) = pan_tilt_speeds(speed);
+
+        self.execute(match direction {
+            Direction::Down => Move::Down(tilt_speed),
+            Direction::Up => Move::Up(tilt_speed),
+            Direction::Left => Move::Left(pan_speed),
+            Direction::Right => Move::Right(pan_speed),
+        })
+    }
+
+    fn stop_pan_tilt(&mut self) -> Result<()> {
+        self.execute(Move::Stop)
+    }
+
+    fn stop_zoom(&mut self) -> Result<()> {
+        self.execute(Zoom::Stop)
+    }
+
+    fn stop_focus(&mut self) -> Result<()> {
+        self.execute(Focus::Stop)
+    }
+
+    fn set_preset(&mut self, preset: u8) -> Result<()> {
+        self.execute(Preset::Set(preset))
+    }
+
+    fn go_to_preset(&mut self, preset: u8) -> Result<()> {
+        self.execute(Preset::Recall(preset))
+    }
+
+    fn set_preset_speed(&mut self, preset: u8, speed: u8) -> Result<()> {
+        self.execute(PresetSpeed { preset, speed })
+    }
+
+    fn set_tally(&mut self, state: bool) -> Result<()> {
+        self.execute(Tally::from(state))
+    }
+
+    fn open_menu(&mut self) -> Result<()> {
+        self.execute(Menu::On)
+    }
+
+    fn close_menu(&mut self) -> Result<()> {
+        self.execute(Menu::Off)
+    }
+
+    fn menu_enter(&mut self) -> Result<()> {
+        self.execute(MenuEnter)
+    }
+
+    fn menu_back(&mut self) -> Result<()> {
+        // 0x10 in the menu command toggles the menu rather than stepping back, and VISCA has
+        // no standard back or cancel
+        Err(Error::Unsupported(String::from("Menu back")))
+    }
+
+    fn menu_navigate(&mut self, direction: Direction) -> Result<()> {
+        // Each drive command moves the highlight by one item, so stop straight away
+        self.pan_tilt(direction, Speed::Slow)?;
+        self.stop_pan_tilt()
+    }
+
+    fn reset(&mut self, kind: ResetKind) -> Result<()> {
+        match kind {
+            ResetKind::Lens => self.execute(Initialize::Lens),
+            ResetKind::Camera => self.execute(Initialize::Camera),
+            ResetKind::FactoryDefaults => Err(Error::Unsupported(String::from("Factory reset"))),
+        }
+    }
+
+    fn send_raw(&mut self, bytes: &[u8]) -> Result<RawReply> {
+        let received = self.port.lock().expect("mutex poisoned").send_raw(bytes)?;
+
+        let decoded = received
+            .split_inclusive(|byte| *byte == 0xFF)
+            .map(|frame| match Message::try_from(frame) {
+                Ok(message) => format!("{message:?}"),
+                Err(_) => format!("Unrecognized: {}", format_hex(frame)),
+            })
+            .collect();
+
+        Ok(RawReply {
+            bytes: format_hex(&received),
+            decoded: Some(decoded),
+        })
+    }
+
+    fn on_event(&mut self, handler: EventHandler) {
+        if let Some(notifications) = self.notifications.take() {
+            Self::spawn_forwarder(
+                Arc::downgrade(&self.port),
+                self.address,
+                notifications,
+                handler,
+            );
+        }
+    }
+
+    fn interrupter(&self) -> Option<Interrupter> {
+        let port = self.port.lock().expect("mutex poisoned");
+
+        match port.canceller(self.address) {
+            Ok(canceller) => {
+                let canceller = Mutex::new(canceller);
+                Some(Box::new(move || {
+                    canceller.lock().expect("mutex poisoned").cancel()?;
+                    Ok(())
+                }))
+            }
+            Err(error) => {
+                debug!("VISCA cancel unavailable: {error}");
+                None
+            }
+        }
+    }
+}
+
+impl<T: Transport> AsRef<ViscaCamera<T>> for ViscaCamera<T> {
+    fn as_ref(&self) -> &ViscaCamera<T> {
+        self
+    }
+}
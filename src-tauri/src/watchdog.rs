@@ -0,0 +1,92 @@
+use std::{
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use tauri::Manager;
+
+use crate::ui_state::UIState;
+
+const CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Stops the camera when a move or zoom goes without a keepalive for too long, so a hung or
+/// crashed webview can't leave it panning.
+pub fn spawn(app_handle: tauri::AppHandle) {
+    thread::spawn(move || {
+        loop {
+            thread::sleep(CHECK_INTERVAL);
+
+            let state = app_handle.state::<Mutex<UIState>>();
+            let mut ui = state.lock().expect("mutext poisoned");
+
+            // The stop reports, and emits, once it has been sent
+            ui.check_watchdog(&app_handle);
+        }
+    });
+}
+
+/// When the controls last confirmed they are still holding the current move or zoom.
+#[derive(Debug, Default)]
+pub struct Keepalive(Option<Instant>);
+
+impl Keepalive {
+    /// Starts timing a move or zoom, or restarts the timer for the one in progress.
+    pub fn refresh(&mut self, now: Instant) {
+        self.0 = Some(now);
+    }
+
+    pub fn clear(&mut self) {
+        self.0 = None;
+    }
+
+    /// Whether more than `timeout` has passed since the last keepalive. Never true when nothing
+    /// is being held.
+    pub fn expired(&self, now: Instant, timeout: Duration) -> bool {
+        self.0
+            .is_some_and(|keepalive| now.saturating_duration_since(keepalive) > timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_millis(1000);
+
+    #[test]
+    fn test_keepalive_times_out() {
+        let start = Instant::now();
+        let mut keepalive = Keepalive::default();
+
+        keepalive.refresh(start);
+
+        assert!(!keepalive.expired(start + TIMEOUT, TIMEOUT));
+        assert!(keepalive.expired(start + TIMEOUT + Duration::from_millis(1), TIMEOUT));
+    }
+
+    #[test]
+    fn test_refresh_restarts_timeout() {
+        let start = Instant::now();
+        let mut keepalive = Keepalive::default();
+
+        keepalive.refresh(start);
+        keepalive.refresh(start + Duration::from_millis(800));
+
+        assert!(!keepalive.expired(start + Duration::from_millis(1500), TIMEOUT));
+        assert!(keepalive.expired(start + Duration::from_millis(1900), TIMEOUT));
+    }
+
+    #[test]
+    fn test_idle_never_expires() {
+        let start = Instant::now();
+        let mut keepalive = Keepalive::default();
+
+        assert!(!keepalive.expired(start + TIMEOUT * 10, TIMEOUT));
+
+        keepalive.refresh(start);
+        keepalive.clear();
+
+        assert!(!keepalive.expired(start + TIMEOUT * 10, TIMEOUT));
+    }
+}
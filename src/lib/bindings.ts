@@ -10,34 +10,145 @@ export const commands = {
   async getState(): Promise<UIStateEvent> {
     return await TAURI_INVOKE('get_state');
   },
+  async addCamera(
+    name: string,
+    protocol: Protocol,
+    portName: string | null,
+    address: number,
+  ): Promise<void> {
+    await TAURI_INVOKE('add_camera', { name, protocol, portName, address });
+  },
+  async removeCamera(id: number): Promise<void> {
+    await TAURI_INVOKE('remove_camera', { id });
+  },
+  async renameCamera(id: number, name: string): Promise<void> {
+    await TAURI_INVOKE('rename_camera', { id, name });
+  },
+  async selectCamera(id: number): Promise<void> {
+    await TAURI_INVOKE('select_camera', { id });
+  },
   async setPort(portName: string | null): Promise<void> {
     await TAURI_INVOKE('set_port', { portName });
   },
+  async addEndpoint(endpoint: string): Promise<void> {
+    await TAURI_INVOKE('add_endpoint', { endpoint });
+  },
+  async removeEndpoint(endpoint: string): Promise<void> {
+    await TAURI_INVOKE('remove_endpoint', { endpoint });
+  },
+  async setProtocol(protocol: Protocol): Promise<void> {
+    await TAURI_INVOKE('set_protocol', { protocol });
+  },
+  async setAddress(address: number): Promise<void> {
+    await TAURI_INVOKE('set_address', { address });
+  },
   async cameraPower(power: boolean): Promise<void> {
     await TAURI_INVOKE('camera_power', { power });
   },
   async autofocus(autofocus: boolean): Promise<void> {
     await TAURI_INVOKE('autofocus', { autofocus });
   },
-  async goToPreset(preset: number, name: string): Promise<void> {
-    await TAURI_INVOKE('go_to_preset', { preset, name });
+  async goToPreset(preset: number): Promise<void> {
+    await TAURI_INVOKE('go_to_preset', { preset });
+  },
+  async setPreset(preset: number): Promise<void> {
+    await TAURI_INVOKE('set_preset', { preset });
+  },
+  async listPresets(): Promise<PresetConfig[]> {
+    return await TAURI_INVOKE('list_presets');
+  },
+  async createPreset(
+    preset: number,
+    name: string,
+    color: string | null,
+  ): Promise<void> {
+    await TAURI_INVOKE('create_preset', { preset, name, color });
+  },
+  async renamePreset(preset: number, name: string): Promise<void> {
+    await TAURI_INVOKE('rename_preset', { preset, name });
+  },
+  async setPresetColor(preset: number, color: string | null): Promise<void> {
+    await TAURI_INVOKE('set_preset_color', { preset, color });
+  },
+  async reorderPresets(order: number[]): Promise<void> {
+    await TAURI_INVOKE('reorder_presets', { order });
+  },
+  async deletePreset(preset: number): Promise<void> {
+    await TAURI_INVOKE('delete_preset', { preset });
+  },
+  async setPresetSpeed(preset: number, speed: number | null): Promise<void> {
+    await TAURI_INVOKE('set_preset_speed', { preset, speed });
+  },
+  async setTally(tally: boolean): Promise<void> {
+    await TAURI_INVOKE('set_tally', { tally });
+  },
+  async auxiliary(channel: number, state: boolean): Promise<void> {
+    await TAURI_INVOKE('auxiliary', { channel, state });
+  },
+  async setAuxiliaryLabel(channel: number, label: string): Promise<void> {
+    await TAURI_INVOKE('set_auxiliary_label', { channel, label });
+  },
+  async startPattern(pattern: number): Promise<void> {
+    await TAURI_INVOKE('start_pattern', { pattern });
   },
-  async setPreset(preset: number, name: string): Promise<void> {
-    await TAURI_INVOKE('set_preset', { preset, name });
+  async stopPattern(pattern: number): Promise<void> {
+    await TAURI_INVOKE('stop_pattern', { pattern });
   },
-  async moveCamera(direction: string): Promise<void> {
-    await TAURI_INVOKE('move_camera', { direction });
+  async runPattern(pattern: number): Promise<void> {
+    await TAURI_INVOKE('run_pattern', { pattern });
+  },
+  async startTour(): Promise<void> {
+    await TAURI_INVOKE('start_tour');
+  },
+  async stopTour(): Promise<void> {
+    await TAURI_INVOKE('stop_tour');
+  },
+  async resetCamera(kind: ResetKind): Promise<void> {
+    await TAURI_INVOKE('reset_camera', { kind });
+  },
+  async moveCamera(direction: string, speed: Speed): Promise<void> {
+    await TAURI_INVOKE('move_camera', { direction, speed });
   },
   async stopMove(): Promise<void> {
     await TAURI_INVOKE('stop_move');
   },
-  async zoom(direction: string): Promise<void> {
-    await TAURI_INVOKE('zoom', { direction });
+  async zoom(direction: string, speed: Speed): Promise<void> {
+    await TAURI_INVOKE('zoom', { direction, speed });
+  },
+  async setFocusSpeed(speed: Speed): Promise<void> {
+    await TAURI_INVOKE('set_focus_speed', { speed });
   },
   async stopZoom(): Promise<void> {
     await TAURI_INVOKE('stop_zoom');
   },
-  async getPorts(): Promise<string[]> {
+  async emergencyStop(): Promise<void> {
+    await TAURI_INVOKE('emergency_stop');
+  },
+  async keepAlive(): Promise<void> {
+    await TAURI_INVOKE('keep_alive');
+  },
+  async setWatchdogTimeout(timeout: number): Promise<void> {
+    await TAURI_INVOKE('set_watchdog_timeout', { timeout });
+  },
+  async openCameraMenu(): Promise<void> {
+    await TAURI_INVOKE('open_camera_menu');
+  },
+  async closeCameraMenu(): Promise<void> {
+    await TAURI_INVOKE('close_camera_menu');
+  },
+  async cameraMenuEnter(): Promise<void> {
+    await TAURI_INVOKE('camera_menu_enter');
+  },
+  async cameraMenuBack(): Promise<void> {
+    await TAURI_INVOKE('camera_menu_back');
+  },
+  async sendRaw(bytes: string): Promise<RawReply> {
+    return await TAURI_INVOKE('send_raw', { bytes });
+  },
+  async getStatusHistory(): Promise<Status[]> {
+    return await TAURI_INVOKE('get_status_history');
+  },
+  async getPorts(): Promise<PortInfo[]> {
     return await TAURI_INVOKE('get_ports');
   },
 };
@@ -54,14 +165,81 @@ export const events = __makeEvents__<{
 
 /** user-defined types **/
 
+export type AuxiliaryConfig = { channel: number; label: string };
+export type CameraConfig = {
+  id: number;
+  name: string;
+  protocol: Protocol;
+  port: string | null;
+  serial_number: string | null;
+  address: number;
+};
 export type Error =
   | { type: 'NoPortSet' }
+  | { type: 'NoCameraSelected' }
+  | { type: 'InvalidCamera'; data: number }
+  | { type: 'InvalidAddress'; data: number }
+  | { type: 'InvalidHex'; data: string }
+  | { type: 'InvalidEndpoint'; data: string }
+  | { type: 'InvalidAuxiliary'; data: number }
+  | { type: 'InvalidResponse'; data: string }
+  | { type: 'InvalidChecksum'; data: { expected: number; received: number } }
+  | { type: 'NoResponse' }
+  | { type: 'InvalidPreset'; data: number }
+  | { type: 'DuplicatePreset'; data: number }
+  | { type: 'InvalidSpeed'; data: number }
+  | { type: 'InvalidWatchdogTimeout'; data: number }
+  | { type: 'InvalidConfig'; data: string }
+  | { type: 'Unsupported'; data: string }
+  | { type: 'CameraWorkerStopped' }
   | { type: 'Tauri' }
   | { type: 'Store' }
   | { type: 'Io' }
   | { type: 'SerialPort' }
   | { type: 'PelcoD' };
-export type UIStateEvent = { port: string | null; ports: string[] | null; status: string };
+export type PortInfo = {
+  name: string;
+  vid: number | null;
+  pid: number | null;
+  manufacturer: string | null;
+  product: string | null;
+  serial_number: string | null;
+};
+export type PresetConfig = {
+  number: number;
+  name: string;
+  color: string | null;
+  speed: number | null;
+};
+export type Protocol = 'PelcoD' | 'PelcoP';
+export type RawReply = { bytes: string; decoded: string[] | null };
+export type ResetKind = 'Lens' | 'Camera' | 'FactoryDefaults';
+export type Severity = 'Info' | 'Warning' | 'Error';
+export type Speed = 'Slow' | 'Medium' | 'Fast' | 'Turbo';
+export type Status = {
+  kind: StatusKind;
+  severity: Severity;
+  message: string;
+  timestamp: number;
+  command: string | null;
+};
+export type StatusKind = 'Connection' | 'Command' | 'Camera' | 'Config';
+export type UIStateEvent = {
+  port: string | null;
+  ports: PortInfo[] | null;
+  cameras: CameraConfig[];
+  camera: number | null;
+  protocols: Protocol[];
+  presets: PresetConfig[];
+  auxiliaries: AuxiliaryConfig[];
+  endpoints: string[];
+  power: boolean | null;
+  tally: boolean;
+  menu: boolean;
+  watchdog_timeout: number;
+  status: Status;
+  history: Status[];
+};
 
 /** tauri-specta globals **/
 
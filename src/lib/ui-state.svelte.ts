@@ -1,19 +1,86 @@
 import { commands, events, type UIStateEvent } from './bindings';
 
-export interface ReadonlyUIStateEvent extends Readonly<Omit<UIStateEvent, 'ports'>> {
-  readonly ports: readonly string[] | null;
+export interface ReadonlyUIStateEvent
+  extends Readonly<
+    Omit<
+      UIStateEvent,
+      | 'ports'
+      | 'cameras'
+      | 'protocols'
+      | 'presets'
+      | 'auxiliaries'
+      | 'endpoints'
+      | 'status'
+      | 'history'
+    >
+  > {
+  readonly ports: readonly Readonly<
+    NonNullable<UIStateEvent['ports']>[number]
+  >[] | null;
+  readonly cameras: readonly Readonly<UIStateEvent['cameras'][number]>[];
+  readonly protocols: readonly UIStateEvent['protocols'][number][];
+  readonly presets: readonly Readonly<UIStateEvent['presets'][number]>[];
+  readonly auxiliaries: readonly Readonly<
+    UIStateEvent['auxiliaries'][number]
+  >[];
+  readonly endpoints: readonly string[];
+  readonly status: Readonly<UIStateEvent['status']>;
+  readonly history: readonly Readonly<UIStateEvent['history'][number]>[];
 }
 
 const state: UIStateEvent = $state({
   port: null,
   ports: null,
-  status: 'Disconnected',
+  cameras: [],
+  camera: null,
+  protocols: [],
+  presets: [],
+  auxiliaries: [],
+  endpoints: [],
+  power: null,
+  tally: false,
+  menu: false,
+  watchdog_timeout: 1000,
+  status: {
+    kind: 'Connection',
+    severity: 'Info',
+    message: 'Disconnected',
+    timestamp: 0,
+    command: null,
+  },
+  history: [],
 });
 
-function set({ port, ports, status }: UIStateEvent) {
+function set({
+  port,
+  ports,
+  cameras,
+  camera,
+  protocols,
+  presets,
+  auxiliaries,
+  endpoints,
+  power,
+  tally,
+  menu,
+  watchdog_timeout,
+  status,
+  history,
+}: UIStateEvent) {
   state.port = port;
   state.ports = ports;
+  state.cameras = cameras;
+  state.camera = camera;
+  state.protocols = protocols;
+  state.presets = presets;
+  state.auxiliaries = auxiliaries;
+  state.endpoints = endpoints;
+  state.power = power;
+  state.tally = tally;
+  state.menu = menu;
+  state.watchdog_timeout = watchdog_timeout;
   state.status = status;
+  state.history = history;
 }
 
 // eslint-disable-next-line unicorn/prefer-top-level-await
@@ -21,3 +88,8 @@ void commands.getState().then(set);
 void events.uiStateEvent.listen((event) => set(event.payload));
 
 export const uiState: ReadonlyUIStateEvent = state;
+
+/** The configuration of the camera commands are sent to, if one is selected. */
+export function activeCamera() {
+  return uiState.cameras.find(({ id }) => id === uiState.camera);
+}